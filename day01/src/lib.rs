@@ -1,4 +1,237 @@
+use std::{error::Error, fmt, fs, io, path::Path, str::FromStr};
+
+use aho_corasick::AhoCorasick;
 use aoc_traits::AdventOfCodeDay;
+use utils::parse::{self, ParseError};
+
+const ENGLISH: [&str; 9] = [
+    "one", "two", "three", "four", "five", "six", "seven", "eight", "nine",
+];
+
+const GERMAN: [&str; 9] = [
+    "eins", "zwei", "drei", "vier", "fünf", "sechs", "sieben", "acht", "neun",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Calibration {
+    pub first: usize,
+    pub last: usize,
+    pub value: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoDigitFound {
+    pub line: usize,
+}
+
+impl fmt::Display for NoDigitFound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no digit found on line {}", self.line)
+    }
+}
+
+impl Error for NoDigitFound {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineReport {
+    /// 1-based line number.
+    pub line: usize,
+    pub calibration: Result<Calibration, NoDigitFound>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Fail on the first line without a digit.
+    Strict,
+    /// Skip lines without a digit.
+    Lenient,
+}
+
+/// Maps spelled out digits to their values, in addition to the digits `0` to `9`.
+pub struct DigitVocabulary {
+    values: Vec<usize>,
+    automaton: AhoCorasick,
+}
+
+impl DigitVocabulary {
+    pub fn new<S: AsRef<str>>(words: impl IntoIterator<Item = (S, usize)>) -> Self {
+        let (mut patterns, mut values): (Vec<String>, Vec<usize>) =
+            (0..10).map(|digit| (digit.to_string(), digit)).unzip();
+        for (word, value) in words {
+            patterns.push(word.as_ref().to_string());
+            values.push(value);
+        }
+        let automaton = AhoCorasick::new(patterns).expect("vocabulary too large");
+        DigitVocabulary { values, automaton }
+    }
+
+    /// Only the digits `0` to `9`, as in part 1.
+    pub fn digits_only() -> Self {
+        Self::new(std::iter::empty::<(&str, usize)>())
+    }
+
+    pub fn english() -> Self {
+        Self::new(ENGLISH.into_iter().zip(1..))
+    }
+
+    pub fn german() -> Self {
+        Self::new(GERMAN.into_iter().zip(1..))
+    }
+
+    /// Loads a vocabulary with one `word: value` pair per line.
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        fs::read_to_string(path)?
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// All digits in `line` ordered by position, including overlapping words like `eightwo`.
+    pub fn digits(&self, line: &str) -> Vec<usize> {
+        let mut matches: Vec<_> = self
+            .automaton
+            .find_overlapping_iter(line)
+            .map(|m| (m.start(), self.values[m.pattern().as_usize()]))
+            .collect();
+        matches.sort();
+        matches.into_iter().map(|(_, value)| value).collect()
+    }
+
+    pub fn calibration(&self, line: &str) -> Option<Calibration> {
+        let digits = self.digits(line);
+        let first = *digits.first()?;
+        let last = *digits.last()?;
+        Some(Calibration {
+            first,
+            last,
+            value: first * 10 + last,
+        })
+    }
+
+    /// First and last digit of `line` combined into a two digit number.
+    pub fn calibration_value(&self, line: &str) -> Option<usize> {
+        self.calibration(line).map(|c| c.value)
+    }
+}
+
+impl Default for DigitVocabulary {
+    fn default() -> Self {
+        Self::english()
+    }
+}
+
+impl FromStr for DigitVocabulary {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let words = s
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .map(|l| {
+                let (word, value) = parse::key_value(l).map_err(|e| e.rebase(l, s))?;
+                let value = parse::value(value).map_err(|e| e.rebase(value, s))?;
+                Ok((word, value))
+            })
+            .collect::<Result<Vec<_>, ParseError>>()?;
+        Ok(Self::new(words))
+    }
+}
+
+mod scalar {
+    pub fn first_digit(line: &[u8]) -> Option<usize> {
+        line.iter().position(u8::is_ascii_digit)
+    }
+
+    pub fn last_digit(line: &[u8]) -> Option<usize> {
+        line.iter().rposition(u8::is_ascii_digit)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod simd {
+    use std::arch::x86_64::{
+        _mm_and_si128, _mm_cmpgt_epi8, _mm_cmplt_epi8, _mm_loadu_si128, _mm_movemask_epi8,
+        _mm_set1_epi8,
+    };
+
+    use super::scalar;
+
+    /// Bit `i` is set if `chunk[i]` is an ASCII digit.
+    fn digit_mask(chunk: &[u8]) -> u32 {
+        assert_eq!(chunk.len(), 16);
+        // SAFETY: SSE2 is part of the x86_64 baseline and the load reads
+        // exactly the 16 bytes of `chunk`. Bytes >= 0x80 compare as negative,
+        // so only '0'..='9' end up in the mask.
+        unsafe {
+            let bytes = _mm_loadu_si128(chunk.as_ptr().cast());
+            let above = _mm_cmpgt_epi8(bytes, _mm_set1_epi8(b'0' as i8 - 1));
+            let below = _mm_cmplt_epi8(bytes, _mm_set1_epi8(b'9' as i8 + 1));
+            _mm_movemask_epi8(_mm_and_si128(above, below)) as u32
+        }
+    }
+
+    pub fn first_digit(line: &[u8]) -> Option<usize> {
+        let chunks = line.chunks_exact(16);
+        let rest = chunks.remainder();
+        for (i, chunk) in chunks.enumerate() {
+            let mask = digit_mask(chunk);
+            if mask != 0 {
+                return Some(i * 16 + mask.trailing_zeros() as usize);
+            }
+        }
+        scalar::first_digit(rest).map(|j| line.len() - rest.len() + j)
+    }
+
+    pub fn last_digit(line: &[u8]) -> Option<usize> {
+        let chunks = line.rchunks_exact(16);
+        let rest = chunks.remainder();
+        for (i, chunk) in chunks.enumerate() {
+            let mask = digit_mask(chunk);
+            if mask != 0 {
+                return Some(line.len() - (i + 1) * 16 + 31 - mask.leading_zeros() as usize);
+            }
+        }
+        scalar::last_digit(rest)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+use simd::{first_digit, last_digit};
+
+#[cfg(not(all(feature = "simd", target_arch = "x86_64")))]
+use scalar::{first_digit, last_digit};
+
+/// First and last ASCII digit of `line`, scanning in from both ends without allocating.
+pub fn first_last_digits(line: &[u8]) -> Option<(usize, usize)> {
+    let first = line[first_digit(line)?];
+    let last = line[last_digit(line)?];
+    Some(((first - b'0') as usize, (last - b'0') as usize))
+}
+
+/// Calibration of every line of `input`, for auditing inputs.
+pub fn report(input: &str, vocabulary: &DigitVocabulary) -> Vec<LineReport> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, l)| LineReport {
+            line: i + 1,
+            calibration: vocabulary
+                .calibration(l)
+                .ok_or(NoDigitFound { line: i + 1 }),
+        })
+        .collect()
+}
+
+pub fn calibration_sum(
+    input: &str,
+    vocabulary: &DigitVocabulary,
+    mode: Mode,
+) -> Result<usize, NoDigitFound> {
+    report(input, vocabulary)
+        .into_iter()
+        .filter(|r| mode == Mode::Strict || r.calibration.is_ok())
+        .map(|r| r.calibration.map(|c| c.value))
+        .sum()
+}
 
 pub struct Day01Solver;
 
@@ -10,49 +243,19 @@ impl<'a> AdventOfCodeDay<'a> for Day01Solver {
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        input.lines().fold(0, |acc, l| {
-            let digits: Vec<_> = l.chars().filter(|c| c.is_ascii_digit()).collect();
-            let first = *digits.first().unwrap() as usize - 0x30;
-            let last = *digits.last().unwrap() as usize - 0x30;
-
-            acc + first * 10 + last
-        })
+        input
+            .lines()
+            .enumerate()
+            .map(|(i, l)| match first_last_digits(l.as_bytes()) {
+                Some((first, last)) => Ok(first * 10 + last),
+                None => Err(NoDigitFound { line: i + 1 }),
+            })
+            .sum::<Result<_, _>>()
+            .unwrap()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        input.lines().fold(0, |acc, l| {
-            let digits: Vec<usize> = l
-                .chars()
-                .enumerate()
-                .filter_map(|(i, c)| {
-                    if c.is_ascii_digit() {
-                        Some(c as usize - 0x30)
-                    } else if l[i..].starts_with("one") {
-                        Some(1)
-                    } else if l[i..].starts_with("two") {
-                        Some(2)
-                    } else if l[i..].starts_with("three") {
-                        Some(3)
-                    } else if l[i..].starts_with("four") {
-                        Some(4)
-                    } else if l[i..].starts_with("five") {
-                        Some(5)
-                    } else if l[i..].starts_with("six") {
-                        Some(6)
-                    } else if l[i..].starts_with("seven") {
-                        Some(7)
-                    } else if l[i..].starts_with("eight") {
-                        Some(8)
-                    } else if l[i..].starts_with("nine") {
-                        Some(9)
-                    } else {
-                        None
-                    }
-                })
-                .collect();
-
-            acc + digits.first().unwrap() * 10 + digits.last().unwrap()
-        })
+        calibration_sum(input, &DigitVocabulary::english(), Mode::Strict).unwrap()
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
@@ -63,6 +266,7 @@ impl<'a> AdventOfCodeDay<'a> for Day01Solver {
 #[cfg(test)]
 mod test {
     use super::*;
+    use utils::rng::Rng;
 
     #[test]
     fn test_part1() {
@@ -94,4 +298,100 @@ mod test {
             281
         );
     }
+
+    #[test]
+    fn test_overlapping_words() {
+        let vocabulary = DigitVocabulary::english();
+        assert_eq!(vocabulary.digits("eightwothree"), vec![8, 2, 3]);
+        assert_eq!(vocabulary.calibration_value("xtwone"), Some(21));
+        assert_eq!(vocabulary.calibration_value("abc"), None);
+    }
+
+    #[test]
+    fn test_other_vocabularies() {
+        let german = DigitVocabulary::german();
+        assert_eq!(german.calibration_value("xsiebenzweins"), Some(71));
+        assert_eq!(german.calibration_value("fünfzehn3"), Some(53));
+
+        let custom: DigitVocabulary = "
+            uno: 1
+            dos: 2
+            tres: 3
+        "
+        .parse()
+        .unwrap();
+        assert_eq!(custom.calibration_value("tresxunodos"), Some(32));
+        assert_eq!(custom.calibration_value("one"), None);
+
+        let input = "uno: 1\ndos: zwei";
+        let Err(err) = input.parse::<DigitVocabulary>() else {
+            panic!("invalid value accepted");
+        };
+        assert_eq!(err.token, "zwei");
+        assert_eq!(err.position(input), Some((2, 6)));
+    }
+
+    #[test]
+    fn test_report() {
+        let input = "
+            1abc2
+            pqrstu
+            treb7uchet
+        ";
+        let vocabulary = DigitVocabulary::digits_only();
+        let report = report(input.trim(), &vocabulary);
+        assert_eq!(
+            report[0].calibration,
+            Ok(Calibration {
+                first: 1,
+                last: 2,
+                value: 12
+            })
+        );
+        assert_eq!(report[1].calibration, Err(NoDigitFound { line: 2 }));
+        assert_eq!(report[2].calibration.unwrap().value, 77);
+
+        assert_eq!(
+            calibration_sum(input.trim(), &vocabulary, Mode::Strict),
+            Err(NoDigitFound { line: 2 })
+        );
+        assert_eq!(
+            calibration_sum(input.trim(), &vocabulary, Mode::Lenient),
+            Ok(89)
+        );
+    }
+
+    /// Lines of printable ASCII, some with a stray non-ASCII character, digits being rare.
+    fn random_lines(count: usize) -> Vec<String> {
+        let mut rng = Rng::new(0x2545_f491_4f6c_dd1d);
+        (0..count)
+            .map(|_| {
+                let len = rng.below(80);
+                (0..len)
+                    .map(|_| match rng.below(40) {
+                        0 => char::from(b'0' + rng.below(10) as u8),
+                        1 => 'ü',
+                        _ => char::from(b' ' + rng.below(95) as u8),
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_fast_path_matches_vocabulary() {
+        let vocabulary = DigitVocabulary::digits_only();
+        for line in random_lines(10_000) {
+            let expected = vocabulary.calibration(&line).map(|c| (c.first, c.last));
+            assert_eq!(first_last_digits(line.as_bytes()), expected, "{:?}", line);
+            assert_eq!(
+                scalar::first_digit(line.as_bytes()),
+                first_digit(line.as_bytes())
+            );
+            assert_eq!(
+                scalar::last_digit(line.as_bytes()),
+                last_digit(line.as_bytes())
+            );
+        }
+    }
 }
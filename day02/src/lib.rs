@@ -1,84 +1,240 @@
-use std::str::FromStr;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fmt,
+    str::FromStr,
+};
 
 use aoc_traits::AdventOfCodeDay;
+use utils::parse::{self, ParseError};
 
-const RED_MAX: usize = 12;
-const GREEN_MAX: usize = 13;
-const BLUE_MAX: usize = 14;
+/// The bag part 1 asks about.
+pub const PUZZLE_BAG: [(&str, usize); 3] = [("red", 12), ("green", 13), ("blue", 14)];
 
-#[derive(Clone, PartialEq, Eq, Hash)]
-enum Color {
-    Red,
-    Green,
-    Blue,
+/// How many cubes of each colour the bag holds. Colours it does not list count as zero.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bag {
+    cubes: HashMap<String, usize>,
 }
 
-impl FromStr for Color {
-    type Err = ();
+impl Bag {
+    pub fn count(&self, color: &str) -> usize {
+        self.cubes.get(color).copied().unwrap_or(0)
+    }
+}
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s {
-            "red" => Ok(Color::Red),
-            "green" => Ok(Color::Green),
-            "blue" => Ok(Color::Blue),
-            _ => Err(()),
+impl fmt::Display for Bag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut cubes: Vec<_> = self.cubes.iter().collect();
+        cubes.sort();
+        let cubes: Vec<_> = cubes.iter().map(|(c, n)| format!("{} {}", n, c)).collect();
+        write!(f, "{}", cubes.join(", "))
+    }
+}
+
+impl<S: Into<String>> FromIterator<(S, usize)> for Bag {
+    fn from_iter<T: IntoIterator<Item = (S, usize)>>(iter: T) -> Self {
+        Bag {
+            cubes: iter.into_iter().map(|(c, n)| (c.into(), n)).collect(),
         }
     }
 }
 
+fn parse_cubes(s: &str) -> Result<(usize, String), ParseError> {
+    let (num, color) = parse::split(s, " ")?;
+    let num = parse::value(num).map_err(|e| e.rebase(num, s))?;
+    Ok((num, color.trim().to_string()))
+}
+
 pub struct Game {
     id: usize,
-    turns: Vec<Vec<(usize, Color)>>,
+    turns: Vec<Vec<(usize, String)>>,
 }
 
 impl FromStr for Game {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let s = s.trim();
-        let (id_str, turns_str) = s.split_once(": ").unwrap();
-        let id: usize = id_str.split_once(' ').unwrap().1.parse().unwrap();
-        let mut turns = Vec::new();
-        for turn_str in turns_str.split("; ") {
-            let mut turn = Vec::new();
-            for cubes in turn_str.split(", ") {
-                let (num, color) = cubes.split_once(' ').unwrap();
-                turn.push((num.parse().unwrap(), color.parse().unwrap()))
-            }
-            turns.push(turn);
-        }
+        let (id_str, turns_str) = parse::key_value(s)?;
+        let id = parse::labelled(id_str, "Game").map_err(|e| e.rebase(id_str, s))?;
+        let turns = parse::list(turns_str, ';', |turn| parse::list(turn, ',', parse_cubes))
+            .map_err(|e| e.rebase(turns_str, s))?;
         Ok(Game { id, turns })
     }
 }
 
 impl Game {
-    fn valid(&self) -> bool {
-        self.turns.iter().all(|turn| {
-            turn.iter().all(|(num, color)| match color {
-                Color::Red => num.le(&RED_MAX),
-                Color::Green => num.le(&GREEN_MAX),
-                Color::Blue => num.le(&BLUE_MAX),
-            })
-        })
+    pub fn id(&self) -> usize {
+        self.id
     }
 
-    fn fewest_cubes_power(&self) -> usize {
-        let mut red = 0;
-        let mut green = 0;
-        let mut blue = 0;
+    pub fn is_possible(&self, bag: &Bag) -> bool {
+        self.turns
+            .iter()
+            .all(|turn| turn.iter().all(|(num, color)| *num <= bag.count(color)))
+    }
+
+    /// The smallest bag this game is possible with, covering every colour it mentions.
+    pub fn fewest_cubes(&self) -> Bag {
+        let mut cubes: HashMap<String, usize> = HashMap::new();
+        for (num, color) in self.turns.iter().flatten() {
+            let max = cubes.entry(color.clone()).or_default();
+            *max = (*max).max(*num);
+        }
+        Bag { cubes }
+    }
+
+    /// Product of the fewest cubes needed of every colour in `colors`, so a
+    /// colour this game never shows makes the power 0.
+    pub fn fewest_cubes_power<'c>(&self, colors: impl IntoIterator<Item = &'c str>) -> usize {
+        let fewest = self.fewest_cubes();
+        colors
+            .into_iter()
+            .map(|color| fewest.count(color))
+            .product()
+    }
+}
+
+/// A turn that shows more cubes of a colour than the bag holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub game: usize,
+    /// 1-based turn within the game.
+    pub turn: usize,
+    pub color: String,
+    pub shown: usize,
+    pub available: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColorStats {
+    /// Games that show this colour at least once.
+    pub games: usize,
+    /// Turns that show this colour.
+    pub draws: usize,
+    pub total: usize,
+    pub max: usize,
+}
 
-        for turn in self.turns.iter() {
-            for (num, color) in turn {
-                match color {
-                    Color::Red => red = red.max(*num),
-                    Color::Green => green = green.max(*num),
-                    Color::Blue => blue = blue.max(*num),
+impl ColorStats {
+    pub fn mean(&self) -> f64 {
+        self.total as f64 / self.draws as f64
+    }
+}
+
+pub trait GameAnalytics {
+    /// The smallest bag that makes every game possible.
+    fn minimal_bag(&self) -> Bag;
+
+    /// Every colour shown in any game.
+    fn colors(&self) -> BTreeSet<&str>;
+
+    /// Every turn that `bag` cannot satisfy, in game order.
+    fn violations(&self, bag: &Bag) -> Vec<Violation>;
+
+    /// Ids of the games `bag` rules out.
+    fn ruled_out(&self, bag: &Bag) -> Vec<usize>;
+
+    fn color_stats(&self) -> BTreeMap<String, ColorStats>;
+}
+
+impl GameAnalytics for [Game] {
+    fn minimal_bag(&self) -> Bag {
+        let mut cubes: HashMap<String, usize> = HashMap::new();
+        for (num, color) in self.iter().flat_map(|g| g.turns.iter().flatten()) {
+            let max = cubes.entry(color.clone()).or_default();
+            *max = (*max).max(*num);
+        }
+        Bag { cubes }
+    }
+
+    fn colors(&self) -> BTreeSet<&str> {
+        self.iter()
+            .flat_map(|g| g.turns.iter().flatten())
+            .map(|(_, color)| color.as_str())
+            .collect()
+    }
+
+    fn violations(&self, bag: &Bag) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        for game in self {
+            for (i, turn) in game.turns.iter().enumerate() {
+                for (num, color) in turn {
+                    if *num > bag.count(color) {
+                        violations.push(Violation {
+                            game: game.id,
+                            turn: i + 1,
+                            color: color.clone(),
+                            shown: *num,
+                            available: bag.count(color),
+                        });
+                    }
                 }
             }
         }
+        violations
+    }
+
+    fn ruled_out(&self, bag: &Bag) -> Vec<usize> {
+        self.iter()
+            .filter(|game| !game.is_possible(bag))
+            .map(Game::id)
+            .collect()
+    }
+
+    fn color_stats(&self) -> BTreeMap<String, ColorStats> {
+        let mut stats: BTreeMap<String, ColorStats> = BTreeMap::new();
+        for game in self {
+            for (color, max) in game.fewest_cubes().cubes {
+                let entry = stats.entry(color).or_default();
+                entry.games += 1;
+                entry.max = entry.max.max(max);
+            }
+            for (num, color) in game.turns.iter().flatten() {
+                let entry = stats.get_mut(color).unwrap();
+                entry.draws += 1;
+                entry.total += num;
+            }
+        }
+        stats
+    }
+}
+
+/// The minimal bag, the turns the puzzle bag rules out and per colour statistics.
+pub fn explain(input: &str) -> String {
+    let games = Day02Solver::parse_input(input);
+    let bag = Bag::from_iter(PUZZLE_BAG);
+    let mut out = String::new();
 
-        red * green * blue
+    out += &format!("minimal bag: {}\n", games.minimal_bag());
+    out += &format!("bag {} rules out:\n", bag);
+    for v in games.violations(&bag) {
+        out += &format!(
+            "  game {}, turn {}: {} {} shown, bag holds {}\n",
+            v.game, v.turn, v.shown, v.color, v.available
+        );
     }
+    out += "colours:\n";
+    for (color, stats) in games.color_stats() {
+        out += &format!(
+            "  {}: {} games, {} draws, {} cubes, max {}, mean {:.2}\n",
+            color,
+            stats.games,
+            stats.draws,
+            stats.total,
+            stats.max,
+            stats.mean()
+        );
+    }
+    out
+}
+
+/// Sum of the ids of the games that are possible with `bag`.
+pub fn possible_id_sum(games: &[Game], bag: &Bag) -> usize {
+    games
+        .iter()
+        .filter(|game| game.is_possible(bag))
+        .map(Game::id)
+        .sum()
 }
 
 pub struct Day02Solver;
@@ -91,20 +247,18 @@ impl<'a> AdventOfCodeDay<'a> for Day02Solver {
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        input.iter().fold(
-            0,
-            |acc, game| if game.valid() { acc + game.id } else { acc },
-        )
+        possible_id_sum(input, &Bag::from_iter(PUZZLE_BAG))
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        input
-            .iter()
-            .fold(0, |acc, game| acc + game.fewest_cubes_power())
+        let colors = input.colors();
+        input.iter().fold(0, |acc, game| {
+            acc + game.fewest_cubes_power(colors.iter().copied())
+        })
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.parse().unwrap()).collect()
+        parse::lines(input).unwrap_or_else(|e| panic!("{}", e.describe(input)))
     }
 }
 
@@ -141,4 +295,99 @@ mod test {
             2286
         );
     }
+
+    #[test]
+    fn test_other_colours() {
+        let input = "
+            Game 1: 3 blue, 4 red; 2 yellow
+            Game 2: 1 blue, 5 yellow; 1 purple
+            Game 3: 2 red, 2 green
+        ";
+        let games = Day02Solver::parse_input(input.trim());
+        let bag = Bag::from_iter([("red", 4), ("blue", 3), ("yellow", 5), ("green", 1)]);
+        assert_eq!(possible_id_sum(&games, &bag), 1);
+        assert_eq!(
+            games[1].fewest_cubes(),
+            Bag::from_iter([("blue", 1), ("yellow", 5), ("purple", 1)])
+        );
+        let colors = games.colors();
+        assert_eq!(
+            colors,
+            BTreeSet::from(["blue", "green", "purple", "red", "yellow"])
+        );
+        assert_eq!(games[0].fewest_cubes_power(colors.iter().copied()), 0);
+        assert_eq!(games[1].fewest_cubes_power(colors.iter().copied()), 0);
+        assert_eq!(games[0].fewest_cubes_power(["red", "blue"]), 4 * 3);
+
+        // a game without green has power 0, as before
+        let input = "
+            Game 1: 3 blue, 4 red
+            Game 2: 1 red, 1 green, 1 blue
+        ";
+        assert_eq!(
+            Day02Solver::solve_part2(&Day02Solver::parse_input(input.trim())),
+            1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "line 2, column 16: invalid value `two`")]
+    fn test_parse_error_position() {
+        Day02Solver::parse_input("Game 1: 3 blue\nGame 2: 1 red, two green");
+    }
+
+    #[test]
+    fn test_analytics() {
+        let input = "
+            Game 1: 3 blue, 4 red; 1 red, 2 green, 6 blue; 2 green
+            Game 2: 1 blue, 2 green; 3 green, 4 blue, 1 red; 1 green, 1 blue
+            Game 3: 8 green, 6 blue, 20 red; 5 blue, 4 red, 13 green; 5 green, 1 red
+            Game 4: 1 green, 3 red, 6 blue; 3 green, 6 red; 3 green, 15 blue, 14 red
+            Game 5: 6 red, 1 blue, 3 green; 2 blue, 1 red, 2 green
+        ";
+        let games = Day02Solver::parse_input(input.trim());
+        let bag = Bag::from_iter(PUZZLE_BAG);
+
+        assert_eq!(
+            games.minimal_bag(),
+            Bag::from_iter([("red", 20), ("green", 13), ("blue", 15)])
+        );
+        assert_eq!(games.ruled_out(&bag), vec![3, 4]);
+        assert!(games.ruled_out(&games.minimal_bag()).is_empty());
+        assert_eq!(
+            games.violations(&bag),
+            vec![
+                Violation {
+                    game: 3,
+                    turn: 1,
+                    color: "red".to_string(),
+                    shown: 20,
+                    available: 12
+                },
+                Violation {
+                    game: 4,
+                    turn: 3,
+                    color: "blue".to_string(),
+                    shown: 15,
+                    available: 14
+                },
+                Violation {
+                    game: 4,
+                    turn: 3,
+                    color: "red".to_string(),
+                    shown: 14,
+                    available: 12
+                },
+            ]
+        );
+        assert_eq!(
+            games.color_stats()["green"],
+            ColorStats {
+                games: 5,
+                draws: 13,
+                total: 48,
+                max: 13
+            }
+        );
+    }
 }
@@ -1,100 +1,224 @@
-use std::collections::HashMap;
+use std::ops::{Bound, Range, RangeBounds};
 
 use aoc_traits::AdventOfCodeDay;
+use utils::grid::ByteGrid;
 
-struct Part {
-    number: usize,
-    start: usize,
-    end: usize,
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Number {
+    pub value: usize,
+    pub row: usize,
+    pub cols: Range<usize>,
 }
 
-fn parse_part(line: &[char], index: usize) -> Part {
-    let mut digits = String::new();
-    let mut start = index;
-    let mut end = index;
-    for c in line.iter().take(index).rev() {
-        if c.is_ascii_digit() {
-            start -= 1;
-        } else {
-            break;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Symbol {
+    pub symbol: char,
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregation {
+    Product,
+    Sum,
+    Max,
+}
+
+impl Aggregation {
+    pub fn apply(&self, values: impl Iterator<Item = usize>) -> usize {
+        match self {
+            Aggregation::Product => values.product(),
+            Aggregation::Sum => values.sum(),
+            Aggregation::Max => values.max().unwrap_or(0),
         }
     }
-    for c in line.iter().skip(index) {
-        if c.is_ascii_digit() {
-            end += 1;
-        } else {
-            break;
+}
+
+/// Selects symbols by character and number of adjacent numbers, and
+/// combines each selected symbol's numbers with an [`Aggregation`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SymbolQuery {
+    pub symbol: char,
+    pub parts: (Bound<usize>, Bound<usize>),
+    pub aggregation: Aggregation,
+}
+
+impl SymbolQuery {
+    pub fn new(symbol: char, parts: impl RangeBounds<usize>, aggregation: Aggregation) -> Self {
+        SymbolQuery {
+            symbol,
+            parts: (parts.start_bound().cloned(), parts.end_bound().cloned()),
+            aggregation,
         }
     }
-    for c in line.iter().skip(start).take(end - start) {
-        digits.push(*c)
+
+    /// `*` with exactly two adjacent numbers, multiplied together.
+    pub fn gear_ratio() -> Self {
+        Self::new('*', 2..=2, Aggregation::Product)
+    }
+}
+
+/// Every number and symbol of an engine schematic, and which of them touch.
+///
+/// Numbers and symbols are adjacent if the symbol lies in the ring of cells
+/// around the number's span, diagonals included.
+#[derive(Debug, Clone)]
+pub struct Schematic {
+    numbers: Vec<Number>,
+    symbols: Vec<Symbol>,
+    /// Indices into `numbers` for each symbol.
+    symbol_numbers: Vec<Vec<usize>>,
+    /// Indices into `symbols` for each number.
+    number_symbols: Vec<Vec<usize>>,
+}
+
+impl Schematic {
+    pub fn new(grid: &ByteGrid) -> Self {
+        let mut numbers = Vec::new();
+        let mut symbols = Vec::new();
+        let mut symbol_at = vec![None; grid.width() * grid.height()];
+
+        for (row, line) in grid.rows().enumerate() {
+            let mut col = 0;
+            while col < line.len() {
+                let c = line[col];
+                if c.is_ascii_digit() {
+                    let len = line[col..]
+                        .iter()
+                        .take_while(|c| c.is_ascii_digit())
+                        .count();
+                    let value = std::str::from_utf8(&line[col..col + len])
+                        .unwrap()
+                        .parse()
+                        .unwrap();
+                    numbers.push(Number {
+                        value,
+                        row,
+                        cols: col..col + len,
+                    });
+                    col += len;
+                    continue;
+                }
+                if c != b'.' {
+                    symbol_at[row * grid.width() + col] = Some(symbols.len());
+                    symbols.push(Symbol {
+                        symbol: c as char,
+                        row,
+                        col,
+                    });
+                }
+                col += 1;
+            }
+        }
+
+        let mut symbol_numbers = vec![Vec::new(); symbols.len()];
+        let mut number_symbols = vec![Vec::new(); numbers.len()];
+        for (i, number) in numbers.iter().enumerate() {
+            let rows = number.row.saturating_sub(1)..(number.row + 2).min(grid.height());
+            let cols = number.cols.start.saturating_sub(1)..(number.cols.end + 1).min(grid.width());
+            for row in rows {
+                for col in cols.clone() {
+                    if let Some(j) = symbol_at[row * grid.width() + col] {
+                        symbol_numbers[j].push(i);
+                        number_symbols[i].push(j);
+                    }
+                }
+            }
+        }
+
+        Schematic {
+            numbers,
+            symbols,
+            symbol_numbers,
+            number_symbols,
+        }
+    }
+
+    pub fn numbers(&self) -> &[Number] {
+        &self.numbers
+    }
+
+    pub fn symbols(&self) -> &[Symbol] {
+        &self.symbols
+    }
+
+    /// Numbers adjacent to the symbol at `index` in [`Schematic::symbols`].
+    pub fn numbers_of(&self, index: usize) -> impl Iterator<Item = &Number> {
+        self.symbol_numbers[index].iter().map(|i| &self.numbers[*i])
+    }
+
+    /// Symbols adjacent to the number at `index` in [`Schematic::numbers`].
+    pub fn symbols_of(&self, index: usize) -> impl Iterator<Item = &Symbol> {
+        self.number_symbols[index].iter().map(|i| &self.symbols[*i])
+    }
+
+    /// Numbers adjacent to at least one symbol.
+    pub fn part_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| !symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    /// Numbers adjacent to no symbol.
+    pub fn loose_numbers(&self) -> impl Iterator<Item = &Number> {
+        self.numbers
+            .iter()
+            .zip(&self.number_symbols)
+            .filter(|(_, symbols)| symbols.is_empty())
+            .map(|(number, _)| number)
+    }
+
+    /// `symbol`s whose number of adjacent numbers lies in `parts`, together with those numbers.
+    pub fn symbols_with(
+        &self,
+        symbol: char,
+        parts: impl RangeBounds<usize>,
+    ) -> impl Iterator<Item = (&Symbol, Vec<&Number>)> {
+        self.symbols
+            .iter()
+            .enumerate()
+            .filter(move |(i, s)| {
+                s.symbol == symbol && parts.contains(&self.symbol_numbers[*i].len())
+            })
+            .map(|(i, s)| (s, self.numbers_of(i).collect()))
+    }
+
+    /// `*` symbols with exactly `parts` adjacent numbers, together with those numbers.
+    pub fn gears(&self, parts: usize) -> impl Iterator<Item = (&Symbol, Vec<&Number>)> {
+        self.symbols_with('*', parts..=parts)
+    }
+
+    /// Sum over the selected symbols of their aggregated numbers. Symbols
+    /// without adjacent numbers add nothing, not even an empty product of 1.
+    pub fn query(&self, query: &SymbolQuery) -> usize {
+        self.symbols_with(query.symbol, query.parts)
+            .filter(|(_, numbers)| !numbers.is_empty())
+            .map(|(_, numbers)| query.aggregation.apply(numbers.iter().map(|n| n.value)))
+            .sum()
     }
-    let number = digits.parse().unwrap();
-    Part { number, start, end }
 }
 
 pub struct Day03Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day03Solver {
-    type ParsedInput = Vec<Vec<char>>;
+    type ParsedInput = Schematic;
 
     type Part1Output = usize;
 
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        let mut parts = HashMap::new();
-
-        for (i, line) in input.iter().enumerate() {
-            for (j, c) in line.iter().enumerate() {
-                if (*c != '.') & !c.is_ascii_digit() {
-                    for i_off in 0..=2 {
-                        for j_off in 0..=2 {
-                            let x = (i + i_off).saturating_sub(1).min(input.len() - 1);
-                            let y = (j + j_off).saturating_sub(1).min(line.len() - 1);
-                            let c = input[x][y];
-                            if c.is_ascii_digit() {
-                                let part = parse_part(&input[x], y);
-                                parts.insert((part.start..part.end, x), part.number);
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        parts.values().sum()
+        input.part_numbers().map(|n| n.value).sum()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let mut sum = 0;
-
-        for (i, line) in input.iter().enumerate() {
-            for (j, c) in line.iter().enumerate() {
-                if *c == '*' {
-                    let mut gears = HashMap::new();
-                    for i_off in 0..=2 {
-                        for j_off in 0..=2 {
-                            let x = (i + i_off).saturating_sub(1).min(input.len() - 1);
-                            let y = (j + j_off).saturating_sub(1).min(input[0].len() - 1);
-                            let c = input[x][y];
-                            if c.is_ascii_digit() {
-                                let part = parse_part(&input[x], y);
-                                gears.insert((part.start..part.end, x), part.number);
-                            }
-                        }
-                    }
-
-                    if gears.len() == 2 {
-                        sum += gears.values().product::<usize>();
-                    }
-                }
-            }
-        }
-        sum
+        input.query(&SymbolQuery::gear_ratio())
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.trim().chars().collect()).collect()
+        Schematic::new(&ByteGrid::new(input).unwrap())
     }
 }
 
@@ -141,4 +265,75 @@ mod test {
             467835
         );
     }
+
+    #[test]
+    fn test_graph_queries() {
+        let input = "
+            467..114..
+            ...*......
+            ..35..633.
+            ......#...
+            617*......
+            .....+.58.
+            ..592.....
+            ......755.
+            ...$.*....
+            .664.598..
+        ";
+        let schematic = Day03Solver::parse_input(input.trim());
+        let loose: Vec<_> = schematic.loose_numbers().map(|n| n.value).collect();
+        assert_eq!(loose, vec![114, 58]);
+        let single: Vec<_> = schematic.gears(1).map(|(s, _)| (s.row, s.col)).collect();
+        assert_eq!(single, vec![(4, 3)]);
+        let (index, _) = schematic
+            .numbers()
+            .iter()
+            .enumerate()
+            .find(|(_, n)| n.value == 617)
+            .unwrap();
+        assert_eq!(schematic.symbols_of(index).next().unwrap().symbol, '*');
+    }
+
+    #[test]
+    fn test_repeated_values() {
+        let input = "
+            12*12
+            .....
+            12.12
+            ..#..
+        ";
+        let schematic = Day03Solver::parse_input(input.trim());
+        assert_eq!(Day03Solver::solve_part1(&schematic), 24 + 24);
+        assert_eq!(Day03Solver::solve_part2(&schematic), 144);
+        assert_eq!(schematic.numbers_of(1).count(), 2);
+    }
+
+    #[test]
+    fn test_symbol_queries() {
+        let input = "
+            467..114..
+            ...*......
+            ..35..633.
+            ......#...
+            617*......
+            .....+.58.
+            ..592.....
+            ......755.
+            ...$.*....
+            .664.598..
+        ";
+        let schematic = Day03Solver::parse_input(input.trim());
+        let sum_of_stars = SymbolQuery::new('*', .., Aggregation::Sum);
+        assert_eq!(schematic.query(&sum_of_stars), 467 + 35 + 617 + 755 + 598);
+        let lonely_max = SymbolQuery::new('*', ..2, Aggregation::Max);
+        assert_eq!(schematic.query(&lonely_max), 617);
+        let any_single = SymbolQuery::new('#', 1..=1, Aggregation::Product);
+        assert_eq!(schematic.query(&any_single), 633);
+
+        // the lone `*` at the bottom has no numbers to multiply
+        let schematic = Day03Solver::parse_input("2*3\n...\n.*.");
+        let up_to_two = SymbolQuery::new('*', 0..=2, Aggregation::Product);
+        assert_eq!(schematic.symbols_with('*', 0..=2).count(), 2);
+        assert_eq!(schematic.query(&up_to_two), 6);
+    }
 }
@@ -1,71 +1,122 @@
-use std::{
-    collections::{HashMap, HashSet},
-    str::FromStr,
-};
+use std::{collections::HashSet, str::FromStr};
 
 use aoc_traits::AdventOfCodeDay;
+use utils::parse::{self, ParseError};
 
 #[derive(Clone, Debug)]
 struct Card {
+    id: usize,
     numbers: HashSet<usize>,
     winning: HashSet<usize>,
 }
 
 impl FromStr for Card {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let offset = s.find(": ").unwrap() + ": ".len();
-        let (winning, numbers) = s[offset..].split_once(" | ").unwrap();
+        let (id, numbers) = parse::key_value(s)?;
+        let (winning, numbers) = parse::split(numbers, "|").map_err(|e| e.rebase(numbers, s))?;
+        let numbers_in = |part| {
+            parse::numbers::<usize>(part)
+                .map(|n| n.into_iter().collect())
+                .map_err(|e| e.rebase(part, s))
+        };
         Ok(Card {
-            winning: winning
-                .split_whitespace()
-                .map(|x| x.parse().unwrap())
-                .collect(),
-            numbers: numbers
-                .split_whitespace()
-                .map(|x| x.parse().unwrap())
-                .collect(),
+            id: parse::labelled(id, "Card").map_err(|e| e.rebase(id, s))?,
+            winning: numbers_in(winning)?,
+            numbers: numbers_in(numbers)?,
         })
     }
 }
 
+/// A card reduced to how many of its numbers are winning ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Matches {
+    pub id: usize,
+    pub count: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CardCopies {
+    pub id: usize,
+    /// Instances held in the end, the original included.
+    pub copies: usize,
+    /// `(source card id, copies won from it)` for every card that won copies of this one.
+    pub won_from: Vec<(usize, usize)>,
+}
+
+/// Instances of every card in the end, using a difference array over the
+/// copies each card hands on to the cards below it.
+fn copies(cards: &[Matches]) -> Vec<usize> {
+    let mut gained = vec![0; cards.len() + 1];
+    let mut expired = vec![0; cards.len() + 1];
+    let mut running = 0;
+    let mut copies = Vec::with_capacity(cards.len());
+
+    for (i, card) in cards.iter().enumerate() {
+        running += gained[i];
+        running -= expired[i];
+        let instances = running + 1;
+        copies.push(instances);
+
+        let end = (i + 1 + card.count).min(cards.len());
+        gained[i + 1] += instances;
+        expired[end] += instances;
+    }
+
+    copies
+}
+
+/// How many copies of each card were won in the end, and from which cards.
+pub fn copy_report(cards: &[Matches]) -> Vec<CardCopies> {
+    let copies = copies(cards);
+    let mut report: Vec<_> = cards
+        .iter()
+        .zip(&copies)
+        .map(|(card, copies)| CardCopies {
+            id: card.id,
+            copies: *copies,
+            won_from: Vec::new(),
+        })
+        .collect();
+
+    for (i, card) in cards.iter().enumerate() {
+        for entry in report.iter_mut().skip(i + 1).take(card.count) {
+            entry.won_from.push((card.id, copies[i]));
+        }
+    }
+
+    report
+}
+
 pub struct Day04Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day04Solver {
-    type ParsedInput = Vec<usize>;
+    type ParsedInput = Vec<Matches>;
 
     type Part1Output = usize;
 
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        input.iter().fold(0, |acc, n| match n {
+        input.iter().fold(0, |acc, card| match card.count {
             0 => acc,
             x => acc + (1 << (x - 1)),
         })
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let mut card_copies: HashMap<usize, usize> = HashMap::new();
-        input.iter().enumerate().fold(0, |acc, (i, n)| {
-            for j in 0..*n {
-                *card_copies.entry(i + j + 1).or_default() += 1;
-            }
-
-            let copies = card_copies.remove(&i).unwrap_or(0);
-            for j in 0..*n {
-                *card_copies.entry(i + j + 1).or_default() += copies;
-            }
-            acc + copies + 1
-        })
+        copies(input).iter().sum()
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input
-            .lines()
-            .map(|l| l.trim().parse::<Card>().unwrap())
-            .map(|card| card.numbers.intersection(&card.winning).count())
+        parse::lines::<Card>(input)
+            .unwrap_or_else(|e| panic!("{}", e.describe(input)))
+            .into_iter()
+            .map(|card| Matches {
+                id: card.id,
+                count: card.numbers.intersection(&card.winning).count(),
+            })
             .collect()
     }
 }
@@ -105,4 +156,21 @@ mod test {
             30
         );
     }
+
+    #[test]
+    fn test_copy_report() {
+        let input = "
+            Card 1: 41 48 83 86 17 | 83 86  6 31 17  9 48 53
+            Card 2: 13 32 20 16 61 | 61 30 68 82 17 32 24 19
+            Card 3:  1 21 53 59 44 | 69 82 63 72 16 21 14  1
+            Card 4: 41 92 73 84 69 | 59 84 76 51 58  5 54 83
+            Card 5: 87 83 26 28 32 | 88 30 70 12 93 22 82 36
+            Card 6: 31 18 13 56 72 | 74 77 10 23 35 67 36 11
+        ";
+        let report = copy_report(&Day04Solver::parse_input(input.trim()));
+        let copies: Vec<_> = report.iter().map(|c| c.copies).collect();
+        assert_eq!(copies, vec![1, 2, 4, 8, 14, 1]);
+        assert_eq!(report[3].won_from, vec![(1, 1), (2, 2), (3, 4)]);
+        assert_eq!(report[5].won_from, vec![]);
+    }
 }
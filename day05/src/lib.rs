@@ -1,11 +1,81 @@
-use std::{
-    ops::Range,
-    str::FromStr,
-    sync::Arc,
-    thread::{self},
-};
+use std::{error::Error, fmt, ops::Range, str::FromStr};
 
 use aoc_traits::AdventOfCodeDay;
+use utils::parse::{self, ParseError};
+
+/// A piecewise shift covering `0..usize::MAX`: each `src` range maps onto
+/// the `dst` range of the same length. `usize::MAX` itself, which no almanac
+/// range can contain, maps to itself as in `seed_to_location`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiecewiseMap {
+    /// Sorted by `src`, which tile the whole domain without overlapping.
+    pieces: Vec<(Range<usize>, Range<usize>)>,
+}
+
+impl PiecewiseMap {
+    pub fn pieces(&self) -> &[(Range<usize>, Range<usize>)] {
+        &self.pieces
+    }
+
+    pub fn apply(&self, x: usize) -> usize {
+        let i = self.pieces.partition_point(|(src, _)| src.end <= x);
+        match self.pieces.get(i) {
+            Some((src, dst)) => dst.start + (x - src.start),
+            None => x,
+        }
+    }
+
+    /// Every `x` with `apply(x) == y`.
+    pub fn preimages(&self, y: usize) -> Vec<usize> {
+        if y == usize::MAX {
+            return vec![y];
+        }
+        let mut xs: Vec<_> = self
+            .pieces
+            .iter()
+            .filter(|(_, dst)| dst.contains(&y))
+            .map(|(src, dst)| src.start + (y - dst.start))
+            .collect();
+        xs.sort();
+        xs
+    }
+
+    /// The inverse map, if this one is a bijection.
+    pub fn invert(&self) -> Option<PiecewiseMap> {
+        let mut pieces: Vec<_> = self
+            .pieces
+            .iter()
+            .map(|(src, dst)| (dst.clone(), src.clone()))
+            .collect();
+        pieces.sort_by_key(|(src, _)| src.start);
+
+        let tiles = pieces.first()?.0.start == 0
+            && pieces.last()?.0.end == usize::MAX
+            && pieces.windows(2).all(|w| w[0].0.end == w[1].0.start);
+        tiles.then_some(PiecewiseMap { pieces })
+    }
+}
+
+/// Two source ranges of the same mapping layer that overlap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Overlap {
+    /// Index of the layer, `0` being seed-to-soil.
+    pub layer: usize,
+    pub first: Range<usize>,
+    pub second: Range<usize>,
+}
+
+impl fmt::Display for Overlap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "mapping layer {} has overlapping source ranges {:?} and {:?}",
+            self.layer, self.first, self.second
+        )
+    }
+}
+
+impl Error for Overlap {}
 
 pub struct Almanac {
     seeds: Vec<usize>,
@@ -13,32 +83,27 @@ pub struct Almanac {
 }
 
 impl FromStr for Almanac {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (seeds, rest) = s.split_once("\n\n").unwrap();
-        let seeds: Vec<usize> = seeds
-            .strip_prefix("seeds: ")
-            .unwrap()
-            .split(' ')
-            .map(|s| s.parse().unwrap())
-            .collect();
-
-        let mappings: Vec<Vec<(Range<usize>, Range<usize>)>> = rest
-            .split("\n\n")
-            .map(|p| {
-                let (_, lines) = p.split_once(':').unwrap();
-                let mut mapping = Vec::new();
-                for l in lines.trim().split('\n') {
-                    let mut iter = l.trim().split(' ').map(|x| x.parse().unwrap());
-                    let dst_start = iter.next().unwrap();
-                    let src_start = iter.next().unwrap();
-                    let len = iter.next().unwrap();
-                    mapping.push((src_start..src_start + len, dst_start..dst_start + len));
-                }
-                mapping
+        let mut blocks = parse::blocks(s);
+        let block = parse::next(&mut blocks, s)?;
+        let seeds = parse::header(block, "seeds").map_err(|e| e.rebase(block, s))?;
+        let seeds = parse::numbers(seeds).map_err(|e| e.rebase(seeds, s))?;
+
+        let mappings = blocks
+            .map(|block| {
+                let (_, lines) = parse::key_value(block).map_err(|e| e.rebase(block, s))?;
+                lines
+                    .lines()
+                    .map(|l| {
+                        let [dst_start, src_start, len] =
+                            parse::number_array(l).map_err(|e| e.rebase(l, s))?;
+                        Ok((src_start..src_start + len, dst_start..dst_start + len))
+                    })
+                    .collect()
             })
-            .collect();
+            .collect::<Result<_, _>>()?;
 
         Ok(Almanac { seeds, mappings })
     }
@@ -57,6 +122,100 @@ impl Almanac {
 
         next
     }
+
+    /// All mapping layers combined into a single seed to location map.
+    pub fn compose(&self) -> PiecewiseMap {
+        let mut pieces = vec![(0..usize::MAX, 0..usize::MAX)];
+        for mapping in self.mappings.iter() {
+            pieces = pieces
+                .into_iter()
+                .flat_map(|(seeds, values)| {
+                    let offset = seeds.start;
+                    let base = values.start;
+                    split_ranges(mapping, vec![values])
+                        .into_iter()
+                        .map(move |(from, to)| {
+                            (offset + (from.start - base)..offset + (from.end - base), to)
+                        })
+                })
+                .collect();
+        }
+        pieces.sort_by_key(|(src, _)| src.start);
+        PiecewiseMap { pieces }
+    }
+
+    /// Checks that no mapping layer has overlapping source ranges, where
+    /// `seed_to_location` would silently use the first one.
+    pub fn validate(&self) -> Result<(), Overlap> {
+        for (layer, mapping) in self.mappings.iter().enumerate() {
+            let mut sources: Vec<_> = mapping.iter().map(|(src, _)| src.clone()).collect();
+            sources.sort_by_key(|src| src.start);
+            if let Some(w) = sources.windows(2).find(|w| w[0].end > w[1].start) {
+                return Err(Overlap {
+                    layer,
+                    first: w[0].clone(),
+                    second: w[1].clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Pushes whole seed ranges through every mapping layer, splitting them
+    /// wherever they straddle a mapping boundary.
+    pub fn seed_ranges_to_locations(&self, seeds: Vec<Range<usize>>) -> Vec<Range<usize>> {
+        self.mappings
+            .iter()
+            .fold(seeds, |ranges, mapping| map_ranges(mapping, ranges))
+    }
+}
+
+fn map_ranges(
+    mapping: &[(Range<usize>, Range<usize>)],
+    ranges: Vec<Range<usize>>,
+) -> Vec<Range<usize>> {
+    split_ranges(mapping, ranges)
+        .into_iter()
+        .map(|(_, mapped)| mapped)
+        .collect()
+}
+
+/// Splits `ranges` at the mapping boundaries and pairs every piece with its
+/// image. Empty ranges hold no seeds and are dropped.
+fn split_ranges(
+    mapping: &[(Range<usize>, Range<usize>)],
+    ranges: Vec<Range<usize>>,
+) -> Vec<(Range<usize>, Range<usize>)> {
+    let mut mapped = Vec::new();
+    let mut unmapped: Vec<_> = ranges.into_iter().filter(|r| !r.is_empty()).collect();
+
+    // Like `find` in `seed_to_location`, earlier entries win where entries overlap.
+    for (src, dst) in mapping {
+        let mut rest = Vec::new();
+        for range in unmapped {
+            let start = range.start.max(src.start);
+            let end = range.end.min(src.end);
+            if start >= end {
+                rest.push(range);
+                continue;
+            }
+
+            mapped.push((
+                start..end,
+                dst.start + (start - src.start)..dst.start + (end - src.start),
+            ));
+            if range.start < start {
+                rest.push(range.start..start);
+            }
+            if end < range.end {
+                rest.push(end..range.end);
+            }
+        }
+        unmapped = rest;
+    }
+
+    mapped.extend(unmapped.into_iter().map(|range| (range.clone(), range)));
+    mapped
 }
 
 pub struct Day05Solver;
@@ -78,31 +237,23 @@ impl<'a> AdventOfCodeDay<'a> for Day05Solver {
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let almanac = Arc::new(input);
-
-        thread::scope(|s| {
-            let mut threads = Vec::new();
-            almanac.seeds.chunks(2).for_each(|chunk| {
-                let thread_almanac = Arc::clone(&almanac);
-                let start = chunk[0];
-                let len = chunk[1];
-                threads.push(s.spawn(move || {
-                    (start..start + len)
-                        .map(|seed| thread_almanac.seed_to_location(seed))
-                        .min()
-                        .unwrap()
-                }));
-            });
-            threads
-                .into_iter()
-                .map(|t| t.join().unwrap())
-                .min()
-                .unwrap()
-        })
+        let seeds = input
+            .seeds
+            .chunks(2)
+            .map(|chunk| chunk[0]..chunk[0] + chunk[1])
+            .collect();
+        input
+            .seed_ranges_to_locations(seeds)
+            .iter()
+            .map(|range| range.start)
+            .min()
+            .unwrap()
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.parse::<Almanac>().unwrap()
+        input
+            .parse::<Almanac>()
+            .unwrap_or_else(|e| panic!("{}", e.describe(input)))
     }
 }
 
@@ -195,4 +346,140 @@ mod test {
             46
         );
     }
+
+    /// Every single seed through `seed_to_location`, only feasible for small inputs.
+    fn brute_force_part2(almanac: &Almanac) -> usize {
+        almanac
+            .seeds
+            .chunks(2)
+            .flat_map(|chunk| chunk[0]..chunk[0] + chunk[1])
+            .map(|seed| almanac.seed_to_location(seed))
+            .min()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_part2_matches_brute_force() {
+        let input = "
+            seeds: 0 40 45 30 90 12
+
+            a-to-b map:
+            10 0 5
+            50 3 20
+            0 30 15
+
+            b-to-c map:
+            100 10 10
+            5 60 15
+            20 95 9
+
+            c-to-d map:
+            0 100 7
+            7 0 100
+        ";
+        let almanac = Day05Solver::parse_input(input.trim());
+        assert_eq!(
+            Day05Solver::solve_part2(&almanac),
+            brute_force_part2(&almanac)
+        );
+
+        let ranges = almanac.seed_ranges_to_locations(vec![0..50, 50..102]);
+        let mut locations: Vec<_> = ranges.into_iter().flatten().collect();
+        let mut expected: Vec<_> = (0..102).map(|s| almanac.seed_to_location(s)).collect();
+        locations.sort();
+        expected.sort();
+        assert_eq!(locations, expected);
+
+        // an empty seed range has no location, however small its start
+        let input = "
+            seeds: 0 0 50 2
+
+            a-to-b map:
+            100 0 10
+        ";
+        let almanac = Day05Solver::parse_input(input.trim());
+        assert_eq!(Day05Solver::solve_part2(&almanac), 50);
+        assert_eq!(brute_force_part2(&almanac), 50);
+        assert_eq!(almanac.seed_ranges_to_locations(vec![0..0, 5..5]), vec![]);
+    }
+
+    #[test]
+    fn test_compose_and_invert() {
+        let input = "
+            seeds: 79 14 55 13
+
+            seed-to-soil map:
+            50 98 2
+            52 50 48
+
+            soil-to-fertilizer map:
+            0 15 37
+            37 52 2
+            39 0 15
+
+            fertilizer-to-water map:
+            49 53 8
+            0 11 42
+            42 0 7
+            57 7 4
+
+            water-to-light map:
+            88 18 7
+            18 25 70
+
+            light-to-temperature map:
+            45 77 23
+            81 45 19
+            68 64 13
+
+            temperature-to-humidity map:
+            0 69 1
+            1 0 69
+
+            humidity-to-location map:
+            60 56 37
+            56 93 4
+        ";
+        let almanac = Day05Solver::parse_input(input.trim());
+        assert_eq!(almanac.validate(), Ok(()));
+
+        let composed = almanac.compose();
+        let inverse = composed.invert().unwrap();
+        for seed in (0..200).chain([usize::MAX - 1, usize::MAX]) {
+            let location = almanac.seed_to_location(seed);
+            assert_eq!(composed.apply(seed), location);
+            assert_eq!(inverse.apply(location), seed);
+            assert_eq!(composed.preimages(location), vec![seed]);
+        }
+    }
+
+    #[test]
+    fn test_overlapping_layer() {
+        let input = "
+            seeds: 1 2
+
+            a-to-b map:
+            10 0 5
+            50 3 20
+
+            b-to-c map:
+            0 0 1
+        ";
+        let almanac = Day05Solver::parse_input(input.trim());
+        assert_eq!(
+            almanac.validate(),
+            Err(Overlap {
+                layer: 0,
+                first: 0..5,
+                second: 3..23
+            })
+        );
+
+        // seeds 5..23 move to 52..70, so 5..10 are never reached and 52 is reached twice
+        let composed = almanac.compose();
+        assert_eq!(composed.apply(3), 13);
+        assert_eq!(composed.preimages(52), vec![5, 52]);
+        assert_eq!(composed.preimages(7), vec![]);
+        assert_eq!(composed.invert(), None);
+    }
 }
@@ -1,17 +1,133 @@
+use std::{error::Error, fmt, ops::RangeInclusive};
+
 use aoc_traits::AdventOfCodeDay;
+use utils::parse::{self, ParseError};
 
-#[derive(Debug)]
+/// A race of `time` milliseconds against a `record` distance.
+///
+/// Holding the button for `h` milliseconds leaves the boat at
+/// `start_speed + acceleration * h` for the remaining `time - h`
+/// milliseconds. The puzzle's boats start at rest and gain one millimetre
+/// per millisecond for every millisecond held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Race {
-    time: usize,
-    record: usize,
+    pub time: usize,
+    pub record: usize,
+    pub acceleration: usize,
+    pub start_speed: usize,
 }
 
 impl Race {
-    fn new(time: usize, record: usize) -> Self {
-        Race { time, record }
+    pub fn new(time: usize, record: usize) -> Self {
+        Race {
+            time,
+            record,
+            acceleration: 1,
+            start_speed: 0,
+        }
+    }
+
+    pub fn with_physics(self, acceleration: usize, start_speed: usize) -> Self {
+        Race {
+            acceleration,
+            start_speed,
+            ..self
+        }
+    }
+
+    /// Distance travelled when holding for `hold <= time` milliseconds, or
+    /// `None` if it does not fit in a `u128`.
+    pub fn distance(&self, hold: usize) -> Option<u128> {
+        let speed = (self.acceleration as u128)
+            .checked_mul(hold as u128)?
+            .checked_add(self.start_speed as u128)?;
+        speed.checked_mul((self.time - hold) as u128)
+    }
+
+    /// Whether holding for `hold <= time` milliseconds beats the record.
+    fn beats(&self, hold: i128) -> bool {
+        // a distance too large for a u128 certainly beats a usize record
+        self.distance(hold as usize)
+            .is_none_or(|distance| distance > self.record as u128)
+    }
+
+    /// The hold times that beat the record, if any.
+    ///
+    /// The distance `(v + a * h) * (t - h)` is a downward parabola in `h` whose
+    /// discriminant against the record simplifies to `(a * t + v)^2 - 4 * a * record`.
+    /// Both roots are estimated with an integer square root and then nudged
+    /// onto the outermost winning holds, so the result is exact.
+    ///
+    /// Fails if `(a * t + v)^2` does not fit in a `u128`, which the puzzle's
+    /// physics (`a = 1`, `v = 0`) never hits.
+    pub fn winning_holds(&self) -> Result<Option<RangeInclusive<usize>>, Overflow> {
+        let a = self.acceleration as u128;
+        let v = self.start_speed as u128;
+        let t = self.time as u128;
+        let record = self.record as u128;
+
+        if a == 0 {
+            // a constant speed only loses time while holding
+            let hi = t as i128 - (record / v.max(1) + 1) as i128;
+            return Ok((v > 0 && hi >= 0).then_some(0..=hi as usize));
+        }
+
+        let top = a
+            .checked_mul(t)
+            .and_then(|at| at.checked_add(v))
+            .ok_or(Overflow)?;
+        let square = top.checked_mul(top).ok_or(Overflow)?;
+        // 4 * a * record beyond a u128 is certainly beyond the square
+        let Some(discriminant) = (4 * a)
+            .checked_mul(record)
+            .and_then(|threshold| square.checked_sub(threshold))
+        else {
+            return Ok(None);
+        };
+
+        // top < 2^64 from here on, so none of these overflow an i128
+        let (a, v, t) = (a as i128, v as i128, t as i128);
+        let root = discriminant.isqrt() as i128;
+        let b = a * t - v;
+
+        let mut lo = (b - root).div_euclid(2 * a).max(0);
+        let mut hi = (b + root).div_euclid(2 * a).min(t);
+        while lo <= hi && !self.beats(lo) {
+            lo += 1;
+        }
+        while lo > 0 && self.beats(lo - 1) {
+            lo -= 1;
+        }
+        while hi >= lo && !self.beats(hi) {
+            hi -= 1;
+        }
+        while hi < t && self.beats(hi + 1) {
+            hi += 1;
+        }
+
+        Ok((lo <= hi).then_some(lo as usize..=hi as usize))
+    }
+
+    /// Number of integer hold times that beat the record.
+    pub fn ways_to_win(&self) -> Result<usize, Overflow> {
+        Ok(self
+            .winning_holds()?
+            .map_or(0, |holds| holds.end() - holds.start() + 1))
+    }
+}
+
+/// A race whose physics are too large to solve exactly, see [`Race::winning_holds`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(acceleration * time + start speed)^2 overflows u128")
     }
 }
 
+impl Error for Overflow {}
+
 pub struct Day06Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day06Solver {
@@ -25,60 +141,48 @@ impl<'a> AdventOfCodeDay<'a> for Day06Solver {
         input
             .0
             .iter()
-            .map(|race| {
-                let b = race.time as f32;
-                let c = -(race.record as f32);
-                let tmp = (b.powf(2f32) + 4f32 * c).sqrt();
-                let x0 = (b - tmp) * 0.5;
-                let x1 = (b + tmp) * 0.5;
-                ((x1.ceil() - 1f32) - x0.floor()) as usize
-            })
+            .map(|race| race.ways_to_win().unwrap())
             .product()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let race = &input.1;
-        let b = race.time as f64;
-        let c = -(race.record as f64);
-        let tmp = (b.powf(2f64) + 4f64 * c).sqrt();
-        let x0 = (b - tmp) * 0.5;
-        let x1 = (b + tmp) * 0.5;
-        ((x1.ceil() - 1f64) - x0.floor()) as usize
+        input.1.ways_to_win().unwrap()
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        let (times, distances) = input.trim().split_once('\n').unwrap();
-        let times: Vec<_> = times
-            .trim()
-            .strip_prefix("Time:")
-            .unwrap()
-            .split_whitespace()
-            .collect();
-        let distances: Vec<_> = distances
-            .trim()
-            .strip_prefix("Distance:")
-            .unwrap()
-            .split_whitespace()
-            .collect();
-
-        let race = Race::new(
-            times.join("").parse().unwrap(),
-            distances.join("").parse().unwrap(),
-        );
+        parse_races(input).unwrap_or_else(|e| panic!("{}", e.describe(input)))
+    }
+}
 
-        let races = times
-            .iter()
-            .zip(distances)
-            .map(|(t, d)| Race::new(t.parse().unwrap(), d.parse().unwrap()))
-            .collect();
+/// The separate races and the single race with the digits run together.
+fn parse_races(input: &str) -> Result<(Vec<Race>, Race), ParseError> {
+    let mut lines = input.trim().lines();
+    let line = parse::next(&mut lines, input)?;
+    let times = parse::header(line, "Time").map_err(|e| e.rebase(line, input))?;
+    let line = parse::next(&mut lines, input)?;
+    let distances = parse::header(line, "Distance").map_err(|e| e.rebase(line, input))?;
 
-        (races, race)
-    }
+    let field = |s| {
+        let numbers = parse::numbers(s).map_err(|e| e.rebase(s, input))?;
+        let joined = parse::concatenated(s).map_err(|e| e.rebase(s, input))?;
+        Ok::<(Vec<usize>, usize), ParseError>((numbers, joined))
+    };
+    let (times, time) = field(times)?;
+    let (distances, distance) = field(distances)?;
+
+    let races = times
+        .into_iter()
+        .zip(distances)
+        .map(|(t, d)| Race::new(t, d))
+        .collect();
+
+    Ok((races, Race::new(time, distance)))
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use utils::rng::Rng;
 
     #[test]
     fn test_part1() {
@@ -103,4 +207,99 @@ mod test {
             71503
         );
     }
+
+    #[test]
+    fn test_parse_error_position() {
+        let input = "Time:      7  15   30\nDistance:  9  4O  200";
+        let err = parse_races(input).unwrap_err();
+        assert_eq!(err.describe(input), "line 2, column 15: invalid value `4O`");
+
+        let input = "Time: 99999999999 99999999999\nDistance: 1 2";
+        let err = parse_races(input).unwrap_err();
+        assert_eq!(err.position(input), Some((1, 7)));
+    }
+
+    fn brute_force(race: &Race) -> usize {
+        (0..=race.time)
+            .filter(|hold| race.distance(*hold).unwrap() > race.record as u128)
+            .count()
+    }
+
+    #[test]
+    fn test_matches_brute_force() {
+        let mut rng = Rng::new(0x9e37_79b9_7f4a_7c15);
+        for _ in 0..10_000 {
+            let mut race = Race::new(rng.below(300), 0);
+            if rng.below(2) == 0 {
+                race = race.with_physics(rng.below(4), rng.below(20));
+            }
+            // records around the best distance, including perfect squares
+            let best = (0..=race.time)
+                .map(|h| race.distance(h).unwrap())
+                .max()
+                .unwrap() as usize;
+            race.record = match rng.below(3) {
+                0 => best.saturating_sub(rng.below(3)),
+                _ => rng.below(best + 3),
+            };
+            assert_eq!(race.ways_to_win(), Ok(brute_force(&race)), "{:?}", race);
+        }
+    }
+
+    #[test]
+    fn test_winning_holds() {
+        assert_eq!(Race::new(7, 9).winning_holds(), Ok(Some(2..=5)));
+        assert_eq!(Race::new(30, 200).winning_holds(), Ok(Some(11..=19)));
+        assert_eq!(Race::new(7, 12).winning_holds(), Ok(None));
+
+        // starting at 5 mm/ms, the boat already wins without holding at all
+        let race = Race::new(7, 30).with_physics(2, 5);
+        assert_eq!(race.distance(0), Some(35));
+        assert_eq!(race.winning_holds(), Ok(Some(0..=4)));
+
+        let coasting = Race::new(10, 20).with_physics(0, 3);
+        assert_eq!(coasting.winning_holds(), Ok(Some(0..=3)));
+    }
+
+    #[test]
+    fn test_large_race() {
+        // the best distance is 2^62, which has to beat the record exactly
+        let time = 1usize << 32;
+        let race = Race::new(time, (1 << 31) * (1 << 31));
+        assert_eq!(race.ways_to_win(), Ok(0));
+
+        let race = Race::new(time, (1 << 31) * (1 << 31) - 1);
+        assert_eq!(race.ways_to_win(), Ok(1));
+
+        let race = Race::new(usize::MAX, usize::MAX - 1);
+        assert_eq!(race.ways_to_win(), Ok(usize::MAX - 3));
+    }
+
+    #[test]
+    fn test_large_physics() {
+        for race in [
+            Race::new(usize::MAX, 0).with_physics(1, 5),
+            Race::new(1 << 63, 0).with_physics(2, 1),
+            Race::new(usize::MAX, 1).with_physics(4, 0),
+        ] {
+            assert_eq!(race.winning_holds(), Err(Overflow), "{:?}", race);
+        }
+
+        // a * t + v just below 2^64, with distances beyond a u128 near the peak
+        for race in [
+            Race::new(1 << 40, usize::MAX).with_physics(1 << 23, 12_345),
+            Race::new(1 << 32, 1 << 50).with_physics((1 << 31) - 1, usize::MAX >> 2),
+            Race::new(1 << 20, 7).with_physics(0, usize::MAX),
+        ] {
+            let holds = race.winning_holds().unwrap().unwrap();
+            let (lo, hi) = (*holds.start(), *holds.end());
+            assert!(
+                race.beats(lo as i128) && race.beats(hi as i128),
+                "{:?}",
+                race
+            );
+            assert!(lo == 0 || !race.beats(lo as i128 - 1), "{:?}", race);
+            assert!(hi == race.time || !race.beats(hi as i128 + 1), "{:?}", race);
+        }
+    }
 }
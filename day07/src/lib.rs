@@ -1,9 +1,9 @@
-use std::{cmp::Ordering, str::FromStr};
+use std::{error::Error, fmt, str::FromStr};
 
 use aoc_traits::AdventOfCodeDay;
 
-#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
-enum Card {
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Card {
     Two,
     Three,
     Four,
@@ -19,8 +19,29 @@ enum Card {
     A,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
-enum Type {
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let c = match self {
+            Card::Two => '2',
+            Card::Three => '3',
+            Card::Four => '4',
+            Card::Five => '5',
+            Card::Six => '6',
+            Card::Seven => '7',
+            Card::Eight => '8',
+            Card::Nine => '9',
+            Card::T => 'T',
+            Card::J => 'J',
+            Card::Q => 'Q',
+            Card::K => 'K',
+            Card::A => 'A',
+        };
+        write!(f, "{}", c)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Type {
     HighCard,
     OnePair,
     TwoPair,
@@ -30,12 +51,28 @@ enum Type {
     FiveOfAKind,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub struct Hand {
     cards: Vec<Card>,
     bid: usize,
 }
 
+impl Hand {
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    pub fn bid(&self) -> usize {
+        self.bid
+    }
+}
+
+impl fmt::Display for Hand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.cards.iter().try_for_each(|card| write!(f, "{}", card))
+    }
+}
+
 impl FromStr for Hand {
     type Err = ();
 
@@ -67,47 +104,185 @@ impl FromStr for Hand {
     }
 }
 
-fn get_type(cards: &[Card]) -> Type {
+/// How cards are ranked and which of them are wild.
+///
+/// A hand's type comes from its groups of equal cards, with the wild cards
+/// joining the largest group. Hands of the same type compare card by card
+/// using `order`, with wild cards ranked wherever `order` puts them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleSet {
+    /// Strength of every card, indexed by `Card as usize`.
+    strength: [usize; 13],
+    wild: Vec<Card>,
+}
+
+impl RuleSet {
+    /// `order` lists every card once, from weakest to strongest. Fails on
+    /// the first card listed twice, which leaves another one unranked.
+    pub fn new(order: [Card; 13], wild: Vec<Card>) -> Result<Self, DuplicateCard> {
+        let mut strength = [None; 13];
+        for (i, card) in order.into_iter().enumerate() {
+            if strength[card as usize].replace(i).is_some() {
+                return Err(DuplicateCard(card));
+            }
+        }
+        let strength = strength.map(Option::unwrap);
+        Ok(RuleSet { strength, wild })
+    }
+
+    /// Part 1: no wild cards, `2` to `A`.
+    pub fn standard() -> Self {
+        RuleSet::new(ORDER, Vec::new()).unwrap()
+    }
+
+    /// Part 2: `J` is wild and the weakest card.
+    pub fn jokers() -> Self {
+        let mut order = [Card::J; 13];
+        order[1..10].copy_from_slice(&ORDER[..9]);
+        order[10..].copy_from_slice(&ORDER[10..]);
+        RuleSet::new(order, vec![Card::J]).unwrap()
+    }
+
+    /// The type of `cards` with the wild cards joining the largest group.
+    pub fn hand_type(&self, cards: &[Card]) -> Type {
+        classify(cards, &self.wild)
+    }
+
+    fn sort_key(&self, hand: &Hand) -> (Type, Vec<usize>) {
+        let strengths = hand
+            .cards
+            .iter()
+            .map(|card| self.strength[*card as usize])
+            .collect();
+        (self.hand_type(&hand.cards), strengths)
+    }
+
+    /// Every hand with its rank and winnings, weakest hand first.
+    pub fn ranking<'a>(&self, hands: &'a [Hand]) -> Vec<RankedHand<'a>> {
+        let mut sorted: Vec<_> = hands.iter().collect();
+        sorted.sort_by_cached_key(|hand| self.sort_key(hand));
+        sorted
+            .into_iter()
+            .enumerate()
+            .map(|(i, hand)| RankedHand {
+                rank: i + 1,
+                hand,
+                detected: classify(&hand.cards, &[]),
+                effective: self.hand_type(&hand.cards),
+                bid: hand.bid,
+                winnings: (i + 1) * hand.bid,
+            })
+            .collect()
+    }
+
+    /// Total winnings: every bid times its hand's rank.
+    pub fn winnings(&self, hands: &[Hand]) -> usize {
+        self.ranking(hands).iter().map(|r| r.winnings).sum()
+    }
+}
+
+/// A card listed more than once in a [`RuleSet`]'s order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DuplicateCard(pub Card);
+
+impl fmt::Display for DuplicateCard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "card {} is ranked more than once", self.0)
+    }
+}
+
+impl Error for DuplicateCard {}
+
+/// A row of [`RuleSet::ranking`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RankedHand<'a> {
+    pub rank: usize,
+    pub hand: &'a Hand,
+    /// The type as dealt, without wild cards.
+    pub detected: Type,
+    /// The type after the wild cards joined the largest group.
+    pub effective: Type,
+    pub bid: usize,
+    pub winnings: usize,
+}
+
+fn classify(cards: &[Card], wild_cards: &[Card]) -> Type {
     let mut counts = [0usize; 13];
+    let mut wild = 0;
     for card in cards {
-        counts[card.clone() as usize] += 1;
+        if wild_cards.contains(card) {
+            wild += 1;
+        } else {
+            counts[*card as usize] += 1;
+        }
     }
 
-    let mut counts: Vec<_> = counts.into_iter().filter(|x| *x != 0).collect();
-    counts.sort();
+    let mut groups: Vec<_> = counts.into_iter().filter(|x| *x != 0).collect();
+    groups.sort_by(|a, b| b.cmp(a));
+    match groups.first_mut() {
+        Some(largest) => *largest += wild,
+        None => groups.push(wild),
+    }
 
-    match counts.as_slice() {
-        [5] => Type::FiveOfAKind,
-        [1, 4] => Type::FourOfAKind,
-        [2, 3] => Type::FullHouse,
-        [1, 1, 3] => Type::ThreeOfAKind,
-        [1, 2, 2] => Type::TwoPair,
-        [1, 1, 1, 2] => Type::OnePair,
+    match groups.as_slice() {
+        [5, ..] => Type::FiveOfAKind,
+        [4, ..] => Type::FourOfAKind,
+        [3, 2, ..] => Type::FullHouse,
+        [3, ..] => Type::ThreeOfAKind,
+        [2, 2, ..] => Type::TwoPair,
+        [2, ..] => Type::OnePair,
         _ => Type::HighCard,
     }
 }
 
-fn get_new_type(hand_type: Type, num_wildcard: usize) -> Type {
-    match (hand_type, num_wildcard) {
-        (Type::HighCard, 1) => Type::OnePair,
-        (Type::HighCard, 2) => Type::ThreeOfAKind,
-        (Type::HighCard, 3) => Type::FourOfAKind,
-        (Type::HighCard, 4) => Type::FiveOfAKind,
-        (Type::OnePair, 1) => Type::ThreeOfAKind,
-        (Type::OnePair, 2) => Type::ThreeOfAKind,
-        (Type::OnePair, 3) => Type::FiveOfAKind,
-        (Type::TwoPair, 1) => Type::FullHouse,
-        (Type::TwoPair, 2) => Type::FourOfAKind,
-        (Type::ThreeOfAKind, 1) => Type::FourOfAKind,
-        (Type::ThreeOfAKind, 2) => Type::FiveOfAKind,
-        (Type::ThreeOfAKind, 3) => Type::FourOfAKind,
-        (Type::FullHouse, 2) => Type::FiveOfAKind,
-        (Type::FullHouse, 3) => Type::FiveOfAKind,
-        (Type::FourOfAKind, 1) => Type::FiveOfAKind,
-        (Type::FourOfAKind, 4) => Type::FiveOfAKind,
-        (Type::FiveOfAKind, 5) => Type::FiveOfAKind,
-        _ => panic!(),
+const ORDER: [Card; 13] = [
+    Card::Two,
+    Card::Three,
+    Card::Four,
+    Card::Five,
+    Card::Six,
+    Card::Seven,
+    Card::Eight,
+    Card::Nine,
+    Card::T,
+    Card::J,
+    Card::Q,
+    Card::K,
+    Card::A,
+];
+
+/// The ranking table under both rule sets.
+pub fn explain(input: &str) -> String {
+    let hands = Day07Solver::parse_input(input);
+    let mut out = String::new();
+
+    for (name, rules) in [
+        ("standard", RuleSet::standard()),
+        ("jokers", RuleSet::jokers()),
+    ] {
+        let ranking = rules.ranking(&hands);
+        out += &format!("{} rules:\n", name);
+        out += &format!(
+            "  {:>5}  {:<5}  {:<12}  {:<12}  {:>5}  {:>9}\n",
+            "rank", "hand", "type", "effective", "bid", "winnings"
+        );
+        for r in &ranking {
+            out += &format!(
+                "  {:>5}  {:<5}  {:<12}  {:<12}  {:>5}  {:>9}\n",
+                r.rank,
+                r.hand.to_string(),
+                format!("{:?}", r.detected),
+                format!("{:?}", r.effective),
+                r.bid,
+                r.winnings
+            );
+        }
+        out += &format!(
+            "  total winnings: {}\n",
+            ranking.iter().map(|r| r.winnings).sum::<usize>()
+        );
     }
+    out
 }
 
 pub struct Day07Solver;
@@ -120,66 +295,11 @@ impl<'a> AdventOfCodeDay<'a> for Day07Solver {
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        let mut types: Vec<_> = input
-            .iter()
-            .map(|hand| (get_type(&hand.cards), hand))
-            .collect();
-        types.sort_by(|a, b| match a.0.cmp(&b.0) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => a.1.cards.cmp(&b.1.cards),
-        });
-        types
-            .iter()
-            .enumerate()
-            .map(|(i, (_, hand))| (i + 1) * hand.bid)
-            .sum()
+        RuleSet::standard().winnings(input)
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let mut types: Vec<_> = input
-            .iter()
-            .map(|hand| {
-                let hand_type = get_type(&hand.cards);
-                if hand.cards.contains(&Card::J) {
-                    let mut counts = [0usize; 13];
-                    for card in hand.cards.iter() {
-                        counts[card.clone() as usize] += 1;
-                    }
-                    let num_wildcard = counts[Card::J as usize];
-                    let new_type = get_new_type(hand_type, num_wildcard);
-                    (new_type, hand)
-                } else {
-                    (hand_type, hand)
-                }
-            })
-            .collect();
-        types.sort_by(|a, b| match a.0.cmp(&b.0) {
-            Ordering::Less => Ordering::Less,
-            Ordering::Greater => Ordering::Greater,
-            Ordering::Equal => {
-                for (a, b) in a.1.cards.iter().zip(&b.1.cards) {
-                    if (*a == Card::J) & (*b == Card::J) {
-                        continue;
-                    } else if *a == Card::J {
-                        return Ordering::Less;
-                    } else if *b == Card::J {
-                        return Ordering::Greater;
-                    } else {
-                        match a.cmp(b) {
-                            Ordering::Equal => continue,
-                            x => return x,
-                        }
-                    }
-                }
-                Ordering::Equal
-            }
-        });
-        types
-            .iter()
-            .enumerate()
-            .map(|(i, (_, hand))| (i + 1) * hand.bid)
-            .sum()
+        RuleSet::jokers().winnings(input)
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
@@ -224,4 +344,86 @@ mod test {
             5905
         );
     }
+
+    #[test]
+    fn test_wild_cards() {
+        let hand_type = |rules: &RuleSet, cards: &str| {
+            let hand: Hand = format!("{} 0", cards).parse().unwrap();
+            rules.hand_type(&hand.cards)
+        };
+
+        let jokers = RuleSet::jokers();
+        assert_eq!(hand_type(&jokers, "JJJJJ"), Type::FiveOfAKind);
+        assert_eq!(hand_type(&jokers, "J2J3J"), Type::FourOfAKind);
+        assert_eq!(hand_type(&jokers, "22J33"), Type::FullHouse);
+        assert_eq!(hand_type(&jokers, "2JJ34"), Type::ThreeOfAKind);
+        assert_eq!(hand_type(&jokers, "2345J"), Type::OnePair);
+        assert_eq!(hand_type(&RuleSet::standard(), "2345J"), Type::HighCard);
+
+        // deuces wild, but still ranked lowest
+        let deuces = RuleSet::new(ORDER, vec![Card::Two]).unwrap();
+        assert_eq!(hand_type(&deuces, "22J33"), Type::FourOfAKind);
+        let hands = Day07Solver::parse_input("23456 1\nJ2345 2\n34567 3");
+        // 34567 is the only high card, and J2345 beats 23456 on the first card
+        assert_eq!(deuces.winnings(&hands), 11);
+
+        // an order with two kings and no queen is rejected
+        let mut order = ORDER;
+        order[10] = Card::K;
+        assert_eq!(RuleSet::new(order, Vec::new()), Err(DuplicateCard(Card::K)));
+    }
+
+    #[test]
+    fn test_ranking() {
+        let input = "
+            32T3K 765
+            T55J5 684
+            KK677 28
+            KTJJT 220
+            QQQJA 483
+        ";
+        let hands = Day07Solver::parse_input(input);
+        let ranking = RuleSet::jokers().ranking(&hands);
+        let rows: Vec<_> = ranking
+            .iter()
+            .map(|r| {
+                (
+                    r.rank,
+                    r.hand.to_string(),
+                    r.detected,
+                    r.effective,
+                    r.winnings,
+                )
+            })
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (1, "32T3K".to_string(), Type::OnePair, Type::OnePair, 765),
+                (2, "KK677".to_string(), Type::TwoPair, Type::TwoPair, 56),
+                (
+                    3,
+                    "T55J5".to_string(),
+                    Type::ThreeOfAKind,
+                    Type::FourOfAKind,
+                    2052
+                ),
+                (
+                    4,
+                    "QQQJA".to_string(),
+                    Type::ThreeOfAKind,
+                    Type::FourOfAKind,
+                    1932
+                ),
+                (
+                    5,
+                    "KTJJT".to_string(),
+                    Type::TwoPair,
+                    Type::FourOfAKind,
+                    1100
+                ),
+            ]
+        );
+        assert!(explain(input).contains("total winnings: 5905"));
+    }
 }
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, mem::swap};
+use std::{collections::HashMap, error::Error, fmt};
 
 use aoc_traits::AdventOfCodeDay;
 
@@ -14,25 +14,253 @@ pub struct Input<'a> {
     graph: HashMap<&'a str, Node<'a>>,
 }
 
-fn lcm(first: usize, second: usize) -> usize {
-    first * second / gcd(first, second)
+/// Where one ghost stands on an end node.
+///
+/// The walk is a function of `(node, instruction index)`, so it runs through
+/// `prefix_len` states once and then repeats the next `cycle_len` forever.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GhostCycle<'a> {
+    pub start: &'a str,
+    pub prefix_len: usize,
+    pub cycle_len: usize,
+    /// Steps at which the ghost is on a `..Z` node, before `prefix_len + cycle_len`.
+    pub hits: Vec<usize>,
 }
 
-fn gcd(first: usize, second: usize) -> usize {
-    let mut max = first;
-    let mut min = second;
-    if min > max {
-        swap(&mut max, &mut min);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Hits {
+    Once(usize),
+    /// Every `offset + k * period`.
+    Every {
+        offset: usize,
+        period: usize,
+    },
+}
+
+impl Hits {
+    fn first(&self) -> usize {
+        match self {
+            Hits::Once(step) | Hits::Every { offset: step, .. } => *step,
+        }
+    }
+
+    fn contains(&self, step: usize) -> bool {
+        match *self {
+            Hits::Once(once) => step == once,
+            Hits::Every { offset, period } => {
+                step >= offset && (step - offset).is_multiple_of(period)
+            }
+        }
+    }
+
+    /// The steps in both, or `Ok(None)` if there are none.
+    fn intersect(self, other: Hits) -> Result<Option<Hits>, GhostError> {
+        match (self, other) {
+            (Hits::Once(step), hits) | (hits, Hits::Once(step)) => {
+                Ok(hits.contains(step).then_some(Hits::Once(step)))
+            }
+            (
+                Hits::Every {
+                    offset: a,
+                    period: m,
+                },
+                Hits::Every {
+                    offset: b,
+                    period: n,
+                },
+            ) => {
+                let Some((x, period)) = crt(a, m, b, n)? else {
+                    return Ok(None);
+                };
+                // lift to the first common step both ghosts reach
+                let min = a.max(b);
+                let offset = if x >= min {
+                    x
+                } else {
+                    let k = (min - x).div_ceil(period);
+                    k.checked_mul(period)
+                        .and_then(|k| k.checked_add(x))
+                        .ok_or(GhostError::Overflow)?
+                };
+                Ok(Some(Hits::Every { offset, period }))
+            }
+        }
+    }
+}
+
+/// Why no ghost walk answer could be given.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GhostError {
+    /// The ghost starting here never reaches a `..Z` node.
+    NeverEnds(String),
+    /// Every ghost reaches an end, but never all of them on the same step.
+    NoCommonStep,
+    /// The common step does not fit in a `usize`.
+    Overflow,
+}
+
+impl fmt::Display for GhostError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GhostError::NeverEnds(start) => {
+                write!(f, "ghost from {} never reaches a ..Z node", start)
+            }
+            GhostError::NoCommonStep => {
+                write!(f, "the ghosts are never on ..Z nodes at the same time")
+            }
+            GhostError::Overflow => write!(f, "the common step overflows"),
+        }
+    }
+}
+
+impl Error for GhostError {}
+
+fn ext_gcd(a: i128, b: i128) -> (i128, i128, i128) {
+    if b == 0 {
+        (a, 1, 0)
+    } else {
+        let (g, x, y) = ext_gcd(b, a % b);
+        (g, y, x - a / b * y)
+    }
+}
+
+/// Solves `x = a mod m` and `x = b mod n` for moduli that need not be coprime,
+/// returning `x mod lcm(m, n)` and the lcm.
+fn crt(a: usize, m: usize, b: usize, n: usize) -> Result<Option<(usize, usize)>, GhostError> {
+    let (a, m, b, n) = (a as i128, m as i128, b as i128, n as i128);
+    let (g, p, _) = ext_gcd(m, n);
+    if (b - a) % g != 0 {
+        return Ok(None);
+    }
+
+    let lcm = m / g * n;
+    let k = ((b - a) / g % (n / g)) * (p % (n / g)) % (n / g);
+    let x = (a + m * k).rem_euclid(lcm);
+    let lcm = usize::try_from(lcm).map_err(|_| GhostError::Overflow)?;
+    Ok(Some((x as usize, lcm)))
+}
+
+impl<'a> Input<'a> {
+    fn step(&self, node: &Node<'a>, dir_idx: usize) -> &Node<'a> {
+        match self.dirs[dir_idx] {
+            'L' => self.graph.get(&node.left).unwrap(),
+            _ => self.graph.get(&node.right).unwrap(),
+        }
+    }
+
+    /// The nodes visited from `start` over `steps` steps, starting with `start`
+    /// itself, or `None` if there is no such node.
+    pub fn trace(&self, start: &str, steps: usize) -> Option<Vec<&'a str>> {
+        let mut cur = self.graph.get(start)?;
+        let mut path = vec![cur.label];
+        for step in 0..steps {
+            cur = self.step(cur, step % self.dirs.len());
+            path.push(cur.label);
+        }
+        Some(path)
+    }
+
+    /// The network in Graphviz DOT, with `..A` nodes green and `..Z` nodes red.
+    pub fn to_dot(&self) -> String {
+        let mut labels: Vec<_> = self.graph.keys().collect();
+        labels.sort();
+
+        let mut out = String::from("digraph network {\n");
+        for label in labels.iter().filter(|l| l.ends_with('A')) {
+            out += &format!("    \"{}\" [style=filled, fillcolor=palegreen];\n", label);
+        }
+        for label in labels.iter().filter(|l| l.ends_with('Z')) {
+            out += &format!("    \"{}\" [style=filled, fillcolor=salmon];\n", label);
+        }
+        for label in labels {
+            let node = &self.graph[label];
+            if node.left == node.right {
+                out += &format!("    \"{}\" -> \"{}\" [label=\"LR\"];\n", label, node.left);
+            } else {
+                out += &format!("    \"{}\" -> \"{}\" [label=\"L\"];\n", label, node.left);
+                out += &format!("    \"{}\" -> \"{}\" [label=\"R\"];\n", label, node.right);
+            }
+        }
+        out += "}\n";
+        out
+    }
+
+    /// Walks from `start` until a `(node, instruction index)` state repeats,
+    /// or `None` if there is no such node.
+    pub fn cycle(&self, start: &str) -> Option<GhostCycle<'a>> {
+        self.graph.get(start).map(|node| self.cycle_from(node))
     }
 
-    loop {
-        let res = max % min;
-        if res == 0 {
-            return min;
+    fn cycle_from(&self, start: &Node<'a>) -> GhostCycle<'a> {
+        let mut seen = HashMap::new();
+        let mut hits = Vec::new();
+        let mut cur = start;
+        let mut step = 0;
+        loop {
+            let dir_idx = step % self.dirs.len();
+            if let Some(first) = seen.insert((cur.label, dir_idx), step) {
+                return GhostCycle {
+                    start: start.label,
+                    prefix_len: first,
+                    cycle_len: step - first,
+                    hits,
+                };
+            }
+            if cur.label.ends_with('Z') {
+                hits.push(step);
+            }
+            cur = self.step(cur, dir_idx);
+            step += 1;
+        }
+    }
+
+    /// The first step at which every ghost from a `..A` node stands on a
+    /// `..Z` node, combining all end hits of every ghost's cycle.
+    pub fn ghost_steps(&self) -> Result<usize, GhostError> {
+        let mut common: Option<Vec<Hits>> = None;
+        for start in self.graph.values().filter(|node| node.label.ends_with('A')) {
+            let cycle = self.cycle_from(start);
+            if cycle.hits.is_empty() {
+                return Err(GhostError::NeverEnds(start.label.to_string()));
+            }
+
+            let hits: Vec<_> = cycle
+                .hits
+                .iter()
+                .map(|&step| {
+                    if step < cycle.prefix_len {
+                        Hits::Once(step)
+                    } else {
+                        Hits::Every {
+                            offset: step,
+                            period: cycle.cycle_len,
+                        }
+                    }
+                })
+                .collect();
+
+            common = Some(match common {
+                None => hits,
+                Some(common) => {
+                    let mut both = Vec::new();
+                    for a in &common {
+                        for b in &hits {
+                            both.extend(a.intersect(*b)?);
+                        }
+                    }
+                    both.sort();
+                    both.dedup();
+                    both
+                }
+            });
         }
 
-        max = min;
-        min = res;
+        common
+            .unwrap_or_default()
+            .iter()
+            .map(Hits::first)
+            .min()
+            .ok_or(GhostError::NoCommonStep)
     }
 }
 
@@ -48,41 +276,15 @@ impl<'a> AdventOfCodeDay<'a> for Day08Solver {
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         let mut num = 0;
         let mut cur = input.graph.get("AAA").unwrap();
-        let mut dir_idx = 0;
         while cur.label.ne("ZZZ") {
-            let dir = input.dirs[dir_idx];
-            cur = match dir {
-                'L' => input.graph.get(&cur.left).unwrap(),
-                _ => input.graph.get(&cur.right).unwrap(),
-            };
-            dir_idx = (dir_idx + 1) % input.dirs.len();
+            cur = input.step(cur, num % input.dirs.len());
             num += 1;
         }
         num
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        input
-            .graph
-            .values()
-            .filter(|node| node.label.ends_with('A'))
-            .cloned()
-            .map(|mut cur| {
-                let mut num = 0;
-                let mut dir_idx = 0;
-                while !cur.label.ends_with('Z') {
-                    let dir = input.dirs[dir_idx];
-                    cur = match dir {
-                        'L' => input.graph.get(&cur.left).unwrap().clone(),
-                        _ => input.graph.get(&cur.right).unwrap().clone(),
-                    };
-                    dir_idx = (dir_idx + 1) % input.dirs.len();
-                    num += 1;
-                }
-                num
-            })
-            .reduce(lcm)
-            .unwrap()
+        input.ghost_steps().unwrap()
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
@@ -143,4 +345,95 @@ mod test {
             6
         );
     }
+
+    #[test]
+    fn test_offset_cycles() {
+        // 1Z every 3 steps from step 2, 2Z every 2 steps from step 3 and 3Z
+        // only once at step 5, so the lcm of the first hits (6) is wrong
+        let input = "
+            L
+
+            1A = (1B, 1B)
+            1B = (1Z, 1Z)
+            1Z = (1C, 1C)
+            1C = (1D, 1D)
+            1D = (1Z, 1Z)
+            2A = (2B, 2B)
+            2B = (2C, 2C)
+            2C = (2Z, 2Z)
+            2Z = (2D, 2D)
+            2D = (2Z, 2Z)
+            3A = (3B, 3B)
+            3B = (3C, 3C)
+            3C = (3D, 3D)
+            3D = (3E, 3E)
+            3E = (3Z, 3Z)
+            3Z = (3X, 3X)
+            3X = (3X, 3X)
+        ";
+        let input = Day08Solver::parse_input(input.trim());
+        assert_eq!(
+            input.cycle("1A"),
+            Some(GhostCycle {
+                start: "1A",
+                prefix_len: 2,
+                cycle_len: 3,
+                hits: vec![2],
+            })
+        );
+        assert_eq!(input.cycle("3A").unwrap().hits, vec![5]);
+        assert_eq!(input.cycle("4A"), None);
+        assert_eq!(input.ghost_steps(), Ok(5));
+    }
+
+    #[test]
+    fn test_no_common_step() {
+        // 1Z on even steps, 2Z on steps 3, 7, 11, ...
+        let input = "
+            L
+
+            1A = (1B, 1B)
+            1B = (1Z, 1Z)
+            1Z = (1C, 1C)
+            1C = (1Z, 1Z)
+            2A = (2B, 2B)
+            2B = (2C, 2C)
+            2C = (2Z, 2Z)
+            2Z = (2A, 2A)
+            4A = (4A, 4A)
+        ";
+        let mut input = Day08Solver::parse_input(input.trim());
+        assert_eq!(
+            input.ghost_steps(),
+            Err(GhostError::NeverEnds("4A".to_string()))
+        );
+
+        input.graph.remove("4A");
+        assert_eq!(input.ghost_steps(), Err(GhostError::NoCommonStep));
+    }
+
+    #[test]
+    fn test_trace_and_dot() {
+        let input = "
+            LLR
+
+            AAA = (BBB, BBB)
+            BBB = (AAA, ZZZ)
+            ZZZ = (ZZZ, ZZZ)
+        ";
+        let input = Day08Solver::parse_input(input.trim());
+        assert_eq!(
+            input.trace("AAA", 6),
+            Some(vec!["AAA", "BBB", "AAA", "BBB", "AAA", "BBB", "ZZZ"])
+        );
+        assert_eq!(input.trace("CCC", 6), None);
+
+        let dot = input.to_dot();
+        assert!(dot.starts_with("digraph network {\n"));
+        assert!(dot.contains("    \"AAA\" [style=filled, fillcolor=palegreen];\n"));
+        assert!(dot.contains("    \"ZZZ\" [style=filled, fillcolor=salmon];\n"));
+        assert!(dot.contains("    \"BBB\" -> \"AAA\" [label=\"L\"];\n"));
+        assert!(dot.contains("    \"BBB\" -> \"ZZZ\" [label=\"R\"];\n"));
+        assert!(dot.contains("    \"ZZZ\" -> \"ZZZ\" [label=\"LR\"];\n"));
+    }
 }
@@ -1,53 +1,295 @@
+use std::{error::Error, fmt};
+
 use aoc_traits::AdventOfCodeDay;
 
 pub struct Day09Solver;
 
-fn get_diffs(history: &[i32]) -> Vec<Vec<i32>> {
-    let mut diffs = vec![history.to_vec()];
-    loop {
-        let diff: Vec<_> = diffs
-            .last()
-            .unwrap()
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Extrapolation {
+    pub value: i128,
+    /// Degree of the polynomial through the history, `0` for a constant.
+    pub degree: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExtrapolateError {
+    Empty,
+    /// No row of the difference table is all zeros, so the history does not
+    /// pin down a polynomial. Only [`Polynomial::fit`] reports this.
+    NotPolynomial,
+    /// An intermediate value does not fit in an `i128`.
+    Overflow,
+}
+
+impl fmt::Display for ExtrapolateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExtrapolateError::Empty => write!(f, "empty history"),
+            ExtrapolateError::NotPolynomial => {
+                write!(f, "history never reaches an all-zero difference row")
+            }
+            ExtrapolateError::Overflow => write!(f, "extrapolation overflows i128"),
+        }
+    }
+}
+
+impl Error for ExtrapolateError {}
+
+/// The first value of every row of the difference table, down to the last
+/// row that is not all zeros, and whether an all-zero row was reached before
+/// the rows ran out.
+fn leading_diffs(history: &[i64]) -> Result<(Vec<i128>, bool), ExtrapolateError> {
+    if history.is_empty() {
+        return Err(ExtrapolateError::Empty);
+    }
+
+    let mut row: Vec<i128> = history.iter().map(|x| *x as i128).collect();
+    let mut leading = Vec::new();
+    while row.iter().any(|x| *x != 0) {
+        leading.push(row[0]);
+        row = row
             .windows(2)
-            .map(|w| w[1] - w[0])
-            .collect();
-        if diff.iter().any(|x| *x != 0) {
-            diffs.push(diff);
-        } else {
-            break;
+            .map(|w| w[1].checked_sub(w[0]))
+            .collect::<Option<_>>()
+            .ok_or(ExtrapolateError::Overflow)?;
+    }
+    Ok((leading, !row.is_empty()))
+}
+
+/// The value `k` steps after the first one of `history`, so `k = history.len()`
+/// is the next value and `k = -1` the one before.
+///
+/// Uses Newton's forward difference formula `f(k) = sum_j C(k, j) * d_j`,
+/// where `d_j` heads the `j`-th difference row and `C(k, j)` is the binomial
+/// coefficient extended to negative `k`. All arithmetic is checked.
+pub fn extrapolate(history: &[i64], k: i64) -> Result<Extrapolation, ExtrapolateError> {
+    let (leading, _) = leading_diffs(history)?;
+    let k = k as i128;
+    let mut binomial = 1i128;
+    let mut value = 0i128;
+    for (j, d) in leading.iter().enumerate() {
+        if j > 0 {
+            // C(k, j) = C(k, j - 1) * (k - j + 1) / j, which divides exactly
+            binomial = binomial
+                .checked_mul(k - j as i128 + 1)
+                .ok_or(ExtrapolateError::Overflow)?
+                / j as i128;
+        }
+        value = binomial
+            .checked_mul(*d)
+            .and_then(|term| value.checked_add(term))
+            .ok_or(ExtrapolateError::Overflow)?;
+    }
+
+    Ok(Extrapolation {
+        value,
+        degree: leading.len().saturating_sub(1),
+    })
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// An exact fraction in lowest terms with a positive denominator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ratio {
+    numer: i128,
+    denom: i128,
+}
+
+impl Ratio {
+    /// Panics if `denom` is zero.
+    pub fn new(numer: i128, denom: i128) -> Self {
+        assert!(denom != 0, "zero denominator");
+        let g = gcd(numer, denom) * denom.signum();
+        Ratio {
+            numer: numer / g,
+            denom: denom / g,
+        }
+    }
+
+    pub fn numer(&self) -> i128 {
+        self.numer
+    }
+
+    pub fn denom(&self) -> i128 {
+        self.denom
+    }
+
+    pub fn checked_add(self, other: Ratio) -> Option<Ratio> {
+        let g = gcd(self.denom, other.denom);
+        let numer = self
+            .numer
+            .checked_mul(other.denom / g)?
+            .checked_add(other.numer.checked_mul(self.denom / g)?)?;
+        Some(Ratio::new(
+            numer,
+            (self.denom / g).checked_mul(other.denom)?,
+        ))
+    }
+
+    pub fn checked_mul(self, other: Ratio) -> Option<Ratio> {
+        // cancel crosswise first to keep the products small
+        let g1 = gcd(self.numer, other.denom).max(1);
+        let g2 = gcd(other.numer, self.denom).max(1);
+        Some(Ratio::new(
+            (self.numer / g1).checked_mul(other.numer / g2)?,
+            (self.denom / g2).checked_mul(other.denom / g1)?,
+        ))
+    }
+}
+
+impl From<i64> for Ratio {
+    fn from(n: i64) -> Self {
+        Ratio::new(n as i128, 1)
+    }
+}
+
+impl fmt::Display for Ratio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.denom {
+            1 => write!(f, "{}", self.numer),
+            denom => write!(f, "{}/{}", self.numer, denom),
         }
     }
-    diffs
+}
+
+/// The polynomial through a history, with `history[i]` its value at `x = i`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polynomial {
+    /// Lowest power first, without trailing zeros.
+    coefficients: Vec<Ratio>,
+}
+
+impl Polynomial {
+    /// Recovers the coefficients from the difference table by expanding
+    /// Newton's form `sum_j d_j * x (x - 1) ... (x - j + 1) / j!`.
+    ///
+    /// Unlike [`extrapolate`], this fails with `NotPolynomial` unless the
+    /// table reaches an all-zero row, i.e. the history confirms the degree.
+    pub fn fit(history: &[i64]) -> Result<Self, ExtrapolateError> {
+        let (leading, polynomial) = leading_diffs(history)?;
+        if !polynomial {
+            return Err(ExtrapolateError::NotPolynomial);
+        }
+        let mut coefficients = vec![Ratio::new(0, 1); leading.len()];
+
+        // falling factorial x (x - 1) ... (x - j + 1) and j!
+        let mut falling = vec![1i128];
+        let mut factorial = 1i128;
+        for (j, d) in leading.iter().enumerate() {
+            if j > 0 {
+                let shift = j as i128 - 1;
+                let mut next = vec![0i128; j + 1];
+                for (i, c) in falling.iter().enumerate() {
+                    next[i + 1] += c;
+                    next[i] = c
+                        .checked_mul(shift)
+                        .and_then(|term| next[i].checked_sub(term))
+                        .ok_or(ExtrapolateError::Overflow)?;
+                }
+                falling = next;
+                factorial = factorial
+                    .checked_mul(j as i128)
+                    .ok_or(ExtrapolateError::Overflow)?;
+            }
+
+            for (coefficient, c) in coefficients.iter_mut().zip(&falling) {
+                *coefficient = c
+                    .checked_mul(*d)
+                    .and_then(|term| coefficient.checked_add(Ratio::new(term, factorial)))
+                    .ok_or(ExtrapolateError::Overflow)?;
+            }
+        }
+
+        while coefficients.last().is_some_and(|c| c.numer == 0) {
+            coefficients.pop();
+        }
+        Ok(Polynomial { coefficients })
+    }
+
+    pub fn coefficients(&self) -> &[Ratio] {
+        &self.coefficients
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    /// The value at `x`, or `None` on overflow.
+    pub fn eval(&self, x: Ratio) -> Option<Ratio> {
+        self.coefficients
+            .iter()
+            .rev()
+            .try_fold(Ratio::new(0, 1), |acc, c| {
+                acc.checked_mul(x)?.checked_add(*c)
+            })
+    }
+}
+
+impl fmt::Display for Polynomial {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut terms = self
+            .coefficients
+            .iter()
+            .enumerate()
+            .rev()
+            .filter(|(_, c)| c.numer != 0)
+            .peekable();
+        if terms.peek().is_none() {
+            return write!(f, "0");
+        }
+
+        let mut first = true;
+        for (power, c) in terms {
+            let sign = if c.numer < 0 { "-" } else { "+" };
+            match (first, sign) {
+                (true, "-") => write!(f, "-")?,
+                (true, _) => {}
+                (false, sign) => write!(f, " {} ", sign)?,
+            }
+            first = false;
+
+            let abs = Ratio::new(c.numer.abs(), c.denom);
+            match (power, abs.denom, abs.numer) {
+                (0, _, _) => write!(f, "{}", abs)?,
+                (_, 1, 1) => {}
+                (_, 1, _) => write!(f, "{}", abs)?,
+                _ => write!(f, "({})", abs)?,
+            }
+            match power {
+                0 => {}
+                1 => write!(f, "x")?,
+                _ => write!(f, "x^{}", power)?,
+            }
+        }
+        Ok(())
+    }
 }
 
 impl<'a> AdventOfCodeDay<'a> for Day09Solver {
-    type ParsedInput = Vec<Vec<i32>>;
+    type ParsedInput = Vec<Vec<i64>>;
 
-    type Part1Output = i32;
+    type Part1Output = i128;
 
-    type Part2Output = i32;
+    type Part2Output = i128;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
         input
             .iter()
-            .map(|history| {
-                get_diffs(history)
-                    .iter()
-                    .rev()
-                    .fold(0, |acc, e| acc + e.last().unwrap())
-            })
+            .map(|history| extrapolate(history, history.len() as i64).unwrap().value)
             .sum()
     }
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
         input
             .iter()
-            .map(|history| {
-                get_diffs(history)
-                    .iter()
-                    .rev()
-                    .fold(0, |acc, e| e.first().unwrap() - acc)
-            })
+            .map(|history| extrapolate(history, -1).unwrap().value)
             .sum()
     }
 
@@ -86,4 +328,60 @@ mod test {
             5
         );
     }
+
+    #[test]
+    fn test_extrapolate() {
+        // 2k^2 + 3k + 1
+        let history = [1, 6, 15, 28, 45];
+        let f = |k: i64| 2 * k * k + 3 * k + 1;
+        for k in [-1000, -3, -1, 0, 2, 5, 1000] {
+            assert_eq!(
+                extrapolate(&history, k),
+                Ok(Extrapolation {
+                    value: f(k) as i128,
+                    degree: 2
+                })
+            );
+        }
+
+        assert_eq!(extrapolate(&[7], 9).unwrap().degree, 0);
+        assert_eq!(extrapolate(&[0, 0], 9).unwrap().value, 0);
+        assert_eq!(extrapolate(&[], 1), Err(ExtrapolateError::Empty));
+
+        // alternating extremes double with every difference row
+        let history: Vec<_> = (0..80)
+            .map(|i| if i % 2 == 0 { i64::MAX } else { i64::MIN })
+            .collect();
+        assert_eq!(extrapolate(&history, 80), Err(ExtrapolateError::Overflow));
+    }
+
+    #[test]
+    fn test_polynomial() {
+        let quadratic = Polynomial::fit(&[1, 6, 15, 28, 45]).unwrap();
+        assert_eq!(quadratic.to_string(), "2x^2 + 3x + 1");
+        assert_eq!(quadratic.degree(), 2);
+
+        let triangular = Polynomial::fit(&[0, 1, 3, 6, 10]).unwrap();
+        assert_eq!(triangular.to_string(), "(1/2)x^2 + (1/2)x");
+        assert_eq!(triangular.eval(Ratio::new(1, 2)), Some(Ratio::new(3, 8)));
+
+        let history = [3, 2, -1, -6, -13, -22];
+        let polynomial = Polynomial::fit(&history).unwrap();
+        assert_eq!(polynomial.to_string(), "-x^2 + 3");
+        for k in -5..10 {
+            let value = extrapolate(&history, k).unwrap().value;
+            assert_eq!(polynomial.eval(k.into()), Some(Ratio::new(value, 1)));
+        }
+
+        assert_eq!(Polynomial::fit(&[0, 0]).unwrap().to_string(), "0");
+        assert_eq!(
+            Polynomial::fit(&[1, 2, 4, 8, 16]),
+            Err(ExtrapolateError::NotPolynomial)
+        );
+        assert_eq!(Polynomial::fit(&[5]), Err(ExtrapolateError::NotPolynomial));
+
+        // extrapolation still takes the lowest degree through short histories
+        assert_eq!(extrapolate(&[1, 2], 2).unwrap().value, 3);
+        assert_eq!(extrapolate(&[5], 1).unwrap().value, 5);
+    }
 }
@@ -1,6 +1,15 @@
+use std::{
+    error::Error,
+    fmt,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+};
+
 use aoc_traits::AdventOfCodeDay;
+use utils::{geometry::Polygon, grid::ByteGrid};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 enum Direction {
     North,
     South,
@@ -8,127 +17,428 @@ enum Direction {
     West,
 }
 
-fn find_start_dir(input: &[Vec<char>], col: usize, row: usize) -> Option<Direction> {
-    if (col != 0) & next_dir(Direction::West, input[row][col - 1]).is_some() {
-        Some(Direction::West)
-    } else if (col != input[0].len()) & next_dir(Direction::East, input[row][col + 1]).is_some() {
-        Some(Direction::East)
-    } else if (row != 0) & next_dir(Direction::North, input[row - 1][col]).is_some() {
-        Some(Direction::North)
-    } else if (row != input.len()) & next_dir(Direction::South, input[row + 1][col]).is_some() {
-        Some(Direction::South)
-    } else {
-        None
+const DIRECTIONS: [Direction; 4] = [
+    Direction::North,
+    Direction::South,
+    Direction::East,
+    Direction::West,
+];
+
+const PIPES: [u8; 6] = [b'|', b'-', b'L', b'J', b'7', b'F'];
+
+/// The two sides a pipe opens to.
+fn openings(pipe: u8) -> Option<[Direction; 2]> {
+    match pipe {
+        b'|' => Some([Direction::North, Direction::South]),
+        b'-' => Some([Direction::East, Direction::West]),
+        b'L' => Some([Direction::North, Direction::East]),
+        b'J' => Some([Direction::North, Direction::West]),
+        b'7' => Some([Direction::South, Direction::West]),
+        b'F' => Some([Direction::South, Direction::East]),
+        _ => None,
     }
 }
 
-fn next_dir(dir: Direction, c: char) -> Option<Direction> {
+fn next_dir(dir: Direction, c: u8) -> Option<Direction> {
     match dir {
         Direction::North => match c {
-            '|' => Some(Direction::North),
-            'F' => Some(Direction::East),
-            '7' => Some(Direction::West),
+            b'|' => Some(Direction::North),
+            b'F' => Some(Direction::East),
+            b'7' => Some(Direction::West),
             _ => None,
         },
         Direction::South => match c {
-            '|' => Some(Direction::South),
-            'L' => Some(Direction::East),
-            'J' => Some(Direction::West),
+            b'|' => Some(Direction::South),
+            b'L' => Some(Direction::East),
+            b'J' => Some(Direction::West),
             _ => None,
         },
         Direction::East => match c {
-            '-' => Some(Direction::East),
-            'J' => Some(Direction::North),
-            '7' => Some(Direction::South),
+            b'-' => Some(Direction::East),
+            b'J' => Some(Direction::North),
+            b'7' => Some(Direction::South),
             _ => None,
         },
         Direction::West => match c {
-            '-' => Some(Direction::West),
-            'L' => Some(Direction::North),
-            'F' => Some(Direction::South),
+            b'-' => Some(Direction::West),
+            b'L' => Some(Direction::North),
+            b'F' => Some(Direction::South),
             _ => None,
         },
     }
 }
 
-fn fill_row(data: &[Vec<char>], row: usize, col: usize, clockwise: bool) -> usize {
-    let mut num = 0;
-    let width = data[0].len();
-    if !clockwise {
-        for i in (0..col).rev() {
-            if data[row][i] == '*' {
-                break;
+/// The tile one step in `dir` from `(col, row)`, if it is on the grid.
+fn neighbour(grid: &ByteGrid, dir: Direction, col: usize, row: usize) -> Option<(usize, usize)> {
+    let (col, row) = match dir {
+        Direction::North => (col, row.checked_sub(1)?),
+        Direction::South => (col, row + 1),
+        Direction::East => (col + 1, row),
+        Direction::West => (col.checked_sub(1)?, row),
+    };
+    grid.get(row, col).map(|_| (col, row))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MazeError {
+    NoStart,
+    /// `S` does not have exactly two neighbouring pipes that connect to it.
+    AmbiguousStart {
+        connections: usize,
+    },
+    /// The pipe at `(col, row)` on the way from `S` leads into a tile that
+    /// does not connect back, or off the grid.
+    DeadEnd {
+        col: usize,
+        row: usize,
+    },
+}
+
+impl fmt::Display for MazeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MazeError::NoStart => write!(f, "no S tile"),
+            MazeError::AmbiguousStart { connections } => {
+                write!(f, "S connects to {} pipes instead of 2", connections)
+            }
+            MazeError::DeadEnd { col, row } => {
+                write!(f, "pipe at column {}, row {} leads nowhere", col, row)
             }
-            num += 1;
         }
-    } else {
-        for i in col..width {
-            if data[row][i] == '*' {
+    }
+}
+
+impl Error for MazeError {}
+
+/// A pipe maze with its main loop through `S`.
+#[derive(Debug, Clone)]
+pub struct PipeMaze<'a> {
+    grid: ByteGrid<'a>,
+    start_pipe: u8,
+    /// Positions `(col, row)` of the main loop in walking order, starting at `S`.
+    path: Vec<(usize, usize)>,
+}
+
+impl<'a> PipeMaze<'a> {
+    pub fn new(grid: ByteGrid<'a>) -> Result<Self, MazeError> {
+        let (row, col) = grid.find(b'S').ok_or(MazeError::NoStart)?;
+
+        let connected: Vec<_> = DIRECTIONS
+            .into_iter()
+            .filter(|dir| {
+                neighbour(&grid, *dir, col, row)
+                    .is_some_and(|(c, r)| next_dir(*dir, grid[(r, c)]).is_some())
+            })
+            .collect();
+        let start_pipe = PIPES
+            .into_iter()
+            .find(|pipe| {
+                openings(*pipe).is_some_and(|open| {
+                    connected.len() == 2 && open.iter().all(|d| connected.contains(d))
+                })
+            })
+            .ok_or(MazeError::AmbiguousStart {
+                connections: connected.len(),
+            })?;
+
+        let mut path = vec![(col, row)];
+        let (mut col, mut row) = (col, row);
+        let mut dir = connected[0];
+        loop {
+            (col, row) = neighbour(&grid, dir, col, row).unwrap();
+            if grid[(row, col)] == b'S' {
                 break;
             }
-            num += 1;
+            path.push((col, row));
+            dir = next_dir(dir, grid[(row, col)])
+                .filter(|dir| {
+                    neighbour(&grid, *dir, col, row).is_some_and(|(c, r)| {
+                        grid[(r, c)] == b'S' || next_dir(*dir, grid[(r, c)]).is_some()
+                    })
+                })
+                .ok_or(MazeError::DeadEnd { col, row })?;
+        }
+
+        Ok(PipeMaze {
+            grid,
+            start_pipe,
+            path,
+        })
+    }
+
+    pub fn grid(&self) -> &ByteGrid<'a> {
+        &self.grid
+    }
+
+    /// `(col, row)` of `S`.
+    pub fn start(&self) -> (usize, usize) {
+        self.path[0]
+    }
+
+    /// The pipe hidden under `S`.
+    pub fn start_pipe(&self) -> u8 {
+        self.start_pipe
+    }
+
+    /// The tile at `(col, row)`, with `S` replaced by its pipe.
+    pub fn tile(&self, col: usize, row: usize) -> u8 {
+        match self.grid[(row, col)] {
+            b'S' => self.start_pipe,
+            c => c,
         }
     }
-    num
-}
 
-fn move_in_dir(dir: &Direction, col: &mut usize, row: &mut usize) {
-    match dir {
-        Direction::North => *row -= 1,
-        Direction::South => *row += 1,
-        Direction::East => *col += 1,
-        Direction::West => *col -= 1,
+    /// The main loop as `(col, row)` points in walking order, starting at `S`.
+    pub fn path(&self) -> &[(usize, usize)] {
+        &self.path
+    }
+
+    /// Number of tiles enclosed by the main loop.
+    pub fn enclosed(&self) -> usize {
+        let vertices = self
+            .path
+            .iter()
+            .map(|(col, row)| (*col as i64, *row as i64))
+            .collect();
+        Polygon::new(vertices).interior_points() as usize
+    }
+
+    /// What every tile is, row by row.
+    ///
+    /// Scans each row left to right and flips between outside and inside at
+    /// every loop tile that opens to the north.
+    pub fn tiles(&self) -> Vec<Vec<Tile>> {
+        let mut on_loop = vec![vec![false; self.grid.width()]; self.grid.height()];
+        for (col, row) in &self.path {
+            on_loop[*row][*col] = true;
+        }
+
+        (0..self.grid.height())
+            .map(|row| {
+                let mut inside = false;
+                (0..self.grid.width())
+                    .map(|col| {
+                        let tile = self.tile(col, row);
+                        if on_loop[row][col] {
+                            if matches!(tile, b'|' | b'L' | b'J') {
+                                inside = !inside;
+                            }
+                            Tile::Loop
+                        } else if inside {
+                            Tile::Enclosed
+                        } else if openings(tile).is_some() {
+                            Tile::Junk
+                        } else {
+                            Tile::Outside
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
     }
+
+    /// The maze in box-drawing characters with ANSI colours: the main loop
+    /// bold, junk pipes dimmed and enclosed tiles on a green background.
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        for (row, tiles) in self.tiles().iter().enumerate() {
+            for (col, kind) in tiles.iter().enumerate() {
+                let glyph = match self.tile(col, row) {
+                    b'|' => '│',
+                    b'-' => '─',
+                    b'L' => '└',
+                    b'J' => '┘',
+                    b'7' => '┐',
+                    b'F' => '┌',
+                    _ => ' ',
+                };
+                let style = match kind {
+                    Tile::Loop if (col, row) == self.start() => "\x1b[1;33m",
+                    Tile::Loop => "\x1b[1m",
+                    Tile::Junk => "\x1b[2m",
+                    Tile::Enclosed => "\x1b[42m",
+                    Tile::Outside => "",
+                };
+                out += &format!("{}{}\x1b[0m", style, glyph);
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The same picture as [`PipeMaze::render`] as a binary PPM image, with
+    /// every tile drawn as a 3 by 3 pixel block.
+    pub fn write_ppm(&self, mut out: impl Write) -> io::Result<()> {
+        let tiles = self.tiles();
+        let (width, height) = (self.grid.width(), self.grid.height());
+        write!(out, "P6\n{} {}\n255\n", 3 * width, 3 * height)?;
+
+        for (row, kinds) in tiles.iter().enumerate() {
+            for y in 0..3 {
+                for (col, kind) in kinds.iter().enumerate() {
+                    let open = openings(self.tile(col, row));
+                    let opens = |dir| open.is_some_and(|open| open.contains(&dir));
+                    let (fg, bg) = match kind {
+                        Tile::Loop if (col, row) == self.start() => ([255, 200, 0], [0, 0, 0]),
+                        Tile::Loop => ([255, 255, 255], [0, 0, 0]),
+                        Tile::Junk => ([90, 90, 90], [0, 0, 0]),
+                        Tile::Enclosed => ([90, 90, 90], [0, 140, 60]),
+                        Tile::Outside => ([0, 0, 0], [0, 0, 0]),
+                    };
+                    for x in 0..3 {
+                        let arm = match (x, y) {
+                            (1, 1) => open.is_some(),
+                            (1, 0) => opens(Direction::North),
+                            (1, 2) => opens(Direction::South),
+                            (0, 1) => opens(Direction::West),
+                            (2, 1) => opens(Direction::East),
+                            _ => false,
+                        };
+                        out.write_all(if arm { &fg } else { &bg })?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_ppm(BufWriter::new(File::create(path)?))
+    }
+}
+
+/// How a tile is drawn, see [`PipeMaze::tiles`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tile {
+    Loop,
+    /// A pipe that is not part of the main loop, outside of it.
+    Junk,
+    /// Any tile inside the main loop, which part 2 counts.
+    Enclosed,
+    Outside,
+}
+
+/// [`PipeMaze::render`] of the parsed input.
+pub fn explain(input: &str) -> String {
+    Day10Solver::parse_input(input).render()
 }
 
 pub struct Day10Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day10Solver {
-    type ParsedInput = Vec<Vec<char>>;
+    type ParsedInput = PipeMaze<'a>;
 
     type Part1Output = usize;
 
     type Part2Output = usize;
 
     fn solve_part1(input: &Self::ParsedInput) -> Self::Part1Output {
-        let mut row = input.iter().position(|row| row.contains(&'S')).unwrap();
-        let mut col = input[row].iter().position(|c| *c == 'S').unwrap();
-        let mut dir = find_start_dir(input, col, row).unwrap();
-        move_in_dir(&dir, &mut col, &mut row);
+        input.path().len() / 2
+    }
+
+    fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
+        input.enclosed()
+    }
 
-        let mut len = 1;
-        while input[row][col] != 'S' {
-            dir = next_dir(dir, input[row][col]).unwrap();
-            move_in_dir(&dir, &mut col, &mut row);
-            len += 1;
+    fn parse_input(input: &'a str) -> Self::ParsedInput {
+        PipeMaze::new(ByteGrid::new(input).unwrap()).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_part1() {
+        let input = "
+            -L|F7
+            7S-7|
+            L|7||
+            -L-J|
+            L|-JF
+        ";
+        assert_eq!(
+            Day10Solver::solve_part1(&Day10Solver::parse_input(input.trim())),
+            4
+        );
+    }
+
+    #[test]
+    fn test_part2() {
+        let input = "
+            .F----7F7F7F7F-7....
+            .|F--7||||||||FJ....
+            .||.FJ||||||||L7....
+            FJL7L7LJLJ||LJ.L-7..
+            L--J.L7...LJS7F-7L7.
+            ....F-J..F7FJ|L7L7L7
+            ....L7.F7||L7|.L7L7|
+            .....|FJLJ|FJ|F7|.LJ
+            ....FJL-7.||.||||...
+            ....L---J.LJ.LJLJ...
+        ";
+        assert_eq!(
+            Day10Solver::solve_part2(&Day10Solver::parse_input(input.trim())),
+            8
+        );
+    }
+
+    fn move_in_dir(dir: &Direction, col: &mut usize, row: &mut usize) {
+        match dir {
+            Direction::North => *row -= 1,
+            Direction::South => *row += 1,
+            Direction::East => *col += 1,
+            Direction::West => *col -= 1,
         }
+    }
 
-        len / 2
+    // Part 2 as solved before Pick's theorem: walk the loop, count its turns to
+    // tell which side is inside, and count tiles along each row from there.
+    fn fill_row(data: &[Vec<u8>], row: usize, col: usize, clockwise: bool) -> usize {
+        let mut num = 0;
+        let width = data[0].len();
+        if !clockwise {
+            for i in (0..col).rev() {
+                if data[row][i] == b'*' {
+                    break;
+                }
+                num += 1;
+            }
+        } else {
+            for c in data[row].iter().take(width).skip(col) {
+                if *c == b'*' {
+                    break;
+                }
+                num += 1;
+            }
+        }
+        num
     }
 
-    fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
-        let mut data = input.clone();
-        let mut row = input.iter().position(|row| row.contains(&'S')).unwrap();
-        let mut col = input[row].iter().position(|c| *c == 'S').unwrap();
+    fn turns_part2(maze: &PipeMaze) -> usize {
+        let input = maze.grid();
+        let mut data = input.to_vec();
+        let (mut row, mut col) = input.find(b'S').unwrap();
         let mut loop_pos = Vec::new();
 
-        data[row][col] = '*';
+        data[row][col] = b'*';
 
-        let mut dir = find_start_dir(input, col, row).unwrap();
-        let start_dir = dir.clone();
+        let mut dir = openings(maze.start_pipe()).unwrap()[0];
+        let start_dir = dir;
         move_in_dir(&dir, &mut col, &mut row);
 
         let mut left_turns = 0;
         let mut right_turns = 0;
 
-        while input[row][col] != 'S' {
+        while input[(row, col)] != b'S' {
             loop_pos.push((col, row));
-            data[row][col] = '*';
+            data[row][col] = b'*';
 
-            let new_dir = next_dir(dir.clone(), input[row][col]).unwrap();
+            let new_dir = next_dir(dir, input[(row, col)]).unwrap();
             move_in_dir(&new_dir, &mut col, &mut row);
 
-            match (dir, new_dir.clone()) {
+            match (dir, new_dir) {
                 (Direction::North, Direction::East) => right_turns += 1,
                 (Direction::North, Direction::West) => left_turns += 1,
                 (Direction::South, Direction::East) => left_turns += 1,
@@ -151,44 +461,32 @@ impl<'a> AdventOfCodeDay<'a> for Day10Solver {
             match dir {
                 Direction::North => num += fill_row(&data, row, col, !clockwise),
                 Direction::South => num += fill_row(&data, row, col, clockwise),
-                _ => match next_dir(dir.clone(), input[row][col]).unwrap() {
+                _ => match next_dir(dir, input[(row, col)]).unwrap() {
                     Direction::North => num += fill_row(&data, row, col, !clockwise),
                     Direction::South => num += fill_row(&data, row, col, clockwise),
                     _ => {}
                 },
             }
-            dir = next_dir(dir, input[row][col]).unwrap();
+            dir = next_dir(dir, input[(row, col)]).unwrap();
         }
         num
     }
 
-    fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.trim().chars().collect()).collect()
-    }
-}
-
-#[cfg(test)]
-mod test {
-    use super::*;
-
-    #[test]
-    fn test_part1() {
-        let input = "
-            -L|F7
-            7S-7|
-            L|7||
-            -L-J|
-            L|-JF
-        ";
-        assert_eq!(
-            Day10Solver::solve_part1(&Day10Solver::parse_input(input.trim())),
-            4
-        );
-    }
-
     #[test]
-    fn test_part2() {
-        let input = "
+    fn test_part2_matches_turns() {
+        let inputs = [
+            "
+            ...........
+            .S-------7.
+            .|F-----7|.
+            .||.....||.
+            .||.....||.
+            .|L-7.F-J|.
+            .|..|.|..|.
+            .L--J.L--J.
+            ...........
+            ",
+            "
             .F----7F7F7F7F-7....
             .|F--7||||||||FJ....
             .||.FJ||||||||L7....
@@ -199,10 +497,127 @@ mod test {
             .....|FJLJ|FJ|F7|.LJ
             ....FJL-7.||.||||...
             ....L---J.LJ.LJLJ...
+            ",
+            "
+            FF7FSF7F7F7F7F7F---7
+            L|LJ||||||||||||F--J
+            FL-7LJLJ||||||LJL-77
+            F--JF--7||LJLJ7F7FJ-
+            L---JF-JLJ.||-FJLJJ7
+            |F|F-JF---7F7-L7L|7|
+            |FFJF7L7F-JF7|JL---7
+            7-L-JL7||F7|L7F-7F7|
+            L.L7LFJ|||||FJL7||LJ
+            L7JLJL-JLJLJL--JLJ.L
+            ",
+        ];
+        for input in inputs {
+            let input = Day10Solver::parse_input(input.trim());
+            assert_eq!(Day10Solver::solve_part2(&input), turns_part2(&input));
+        }
+    }
+
+    #[test]
+    fn test_loop_ray_casting() {
+        let input = "
+            FF7FSF7F7F7F7F7F---7
+            L|LJ||||||||||||F--J
+            FL-7LJLJ||||||LJL-77
+            F--JF--7||LJLJ7F7FJ-
+            L---JF-JLJ.||-FJLJJ7
+            |F|F-JF---7F7-L7L|7|
+            |FFJF7L7F-JF7|JL---7
+            7-L-JL7||F7|L7F-7F7|
+            L.L7LFJ|||||FJL7||LJ
+            L7JLJL-JLJLJL--JLJ.L
         ";
+        let maze = Day10Solver::parse_input(input.trim());
+        let polygon = Polygon::new(
+            maze.path()
+                .iter()
+                .map(|(col, row)| (*col as i64, *row as i64))
+                .collect(),
+        );
+        let grid = maze.grid();
+        let enclosed = (0..grid.height())
+            .flat_map(|row| (0..grid.width()).map(move |col| (col as i64, row as i64)))
+            .filter(|p| polygon.contains(*p))
+            .count();
+        assert_eq!(enclosed, 10);
+    }
+
+    #[test]
+    fn test_start_inference() {
+        // S in the bottom right corner used to index past the edge
+        let maze = Day10Solver::parse_input("F-7\n|.|\nL-S");
+        assert_eq!(maze.start(), (2, 2));
+        assert_eq!(maze.start_pipe(), b'J');
+        assert_eq!(maze.tile(2, 2), b'J');
         assert_eq!(
-            Day10Solver::solve_part2(&Day10Solver::parse_input(input.trim())),
-            8
+            maze.path(),
+            [
+                (2, 2),
+                (2, 1),
+                (2, 0),
+                (1, 0),
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 2)
+            ]
+        );
+
+        let maze = |input| PipeMaze::new(ByteGrid::new(input).unwrap()).map(|m| m.start_pipe());
+        assert_eq!(maze("FS7\nL-J"), Ok(b'-'));
+        assert_eq!(maze("..\n.."), Err(MazeError::NoStart));
+        assert_eq!(
+            maze(".|.\n-S-\n.|."),
+            Err(MazeError::AmbiguousStart { connections: 4 })
+        );
+        assert_eq!(
+            maze("S-.\n...\n..."),
+            Err(MazeError::AmbiguousStart { connections: 1 })
         );
+        // walking south first, the last `-` points off the grid
+        assert_eq!(
+            maze("S-7\n|.|\nL--"),
+            Err(MazeError::DeadEnd { col: 2, row: 2 })
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let input = "
+            ..........
+            .S------7.
+            .|F----7|.
+            .||....||.
+            .||....||.
+            .|L-7F-J|.
+            .|..||..|.
+            .L--JL--J.
+            ..........
+        ";
+        let maze = Day10Solver::parse_input(input.trim());
+        let tiles = maze.tiles();
+        let enclosed = tiles
+            .iter()
+            .flatten()
+            .filter(|t| **t == Tile::Enclosed)
+            .count();
+        assert_eq!(enclosed, maze.enclosed());
+        assert_eq!(tiles[3][3], Tile::Outside);
+        assert_eq!(tiles[6][2], Tile::Enclosed);
+
+        let render = maze.render();
+        assert_eq!(render.lines().count(), 9);
+        assert!(render.contains("\x1b[1;33m┌\x1b[0m\x1b[1m─\x1b[0m"));
+        assert!(render.contains("\x1b[42m \x1b[0m"));
+
+        let mut image = Vec::new();
+        maze.write_ppm(&mut image).unwrap();
+        let header = b"P6\n30 27\n255\n";
+        assert!(image.starts_with(header));
+        assert_eq!(image.len(), header.len() + 30 * 27 * 3);
     }
 }
@@ -1,58 +1,64 @@
 use aoc_traits::AdventOfCodeDay;
+use utils::grid::ByteGrid;
 
-fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
-    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
-}
-
-fn solve(input: &[Vec<char>], r: usize) -> usize {
-    let galaxies: Vec<_> = input
+/// Sum of all pairwise distances between `coords`, sorting them in place.
+fn pairwise_sum(coords: &mut [usize]) -> usize {
+    coords.sort_unstable();
+    let mut before = 0;
+    coords
         .iter()
         .enumerate()
-        .flat_map(|(i, row)| {
-            row.iter()
-                .enumerate()
-                .filter_map(|(j, c)| if *c == '#' { Some((j, i)) } else { None })
-                .collect::<Vec<_>>()
+        .map(|(i, c)| {
+            let sum = c * i - before;
+            before += c;
+            sum
         })
-        .collect();
+        .sum()
+}
 
-    let mut y_expansions = vec![r; input.len()];
-    for (i, row) in input.iter().enumerate() {
-        if row.iter().any(|c| *c != '.') {
-            y_expansions[i] = 1;
-        }
-    }
+/// Expanded start of every row or column, given which of them are empty.
+fn offsets(empty: impl Iterator<Item = bool>, r: usize) -> Vec<usize> {
+    empty
+        .scan(0, |offset, empty| {
+            let start = *offset;
+            *offset += if empty { r } else { 1 };
+            Some(start)
+        })
+        .collect()
+}
 
-    let mut x_expansions = vec![r; input[0].len()];
-    for (i, x_expansion) in x_expansions.iter_mut().enumerate() {
-        if (0..input.len()).map(|j| input[j][i]).any(|c| c != '.') {
-            *x_expansion = 1;
-        }
-    }
+/// Sum of the shortest paths between all pairs of galaxies, where every
+/// empty row and column is `r` times as wide.
+///
+/// Each axis is independent: a galaxy's expanded coordinate is a prefix sum
+/// of the row or column widths, and the distances along one axis add up in
+/// a single pass over the sorted coordinates.
+pub fn solve(input: &ByteGrid, r: usize) -> usize {
+    let row_offsets = offsets(input.rows().map(|row| row.iter().all(|c| *c == b'.')), r);
+    let col_offsets = offsets(
+        (0..input.width()).map(|i| input.column(i).all(|c| c == b'.')),
+        r,
+    );
 
-    galaxies
-        .iter()
+    let (mut xs, mut ys): (Vec<_>, Vec<_>) = input
+        .rows()
         .enumerate()
-        .flat_map(|(i, galaxy)| {
-            galaxies
-                .iter()
-                .skip(i + 1)
-                .map(|other| {
-                    let x_adj_galaxy: usize = (0..galaxy.0).map(|x| x_expansions[x]).sum();
-                    let y_adj_galaxy: usize = (0..galaxy.1).map(|y| y_expansions[y]).sum();
-                    let x_adj_other: usize = (0..other.0).map(|x| x_expansions[x]).sum();
-                    let y_adj_other: usize = (0..other.1).map(|y| y_expansions[y]).sum();
-                    distance((x_adj_galaxy, y_adj_galaxy), (x_adj_other, y_adj_other))
-                })
-                .collect::<Vec<_>>()
+        .flat_map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, c)| **c == b'#')
+                .map(move |(j, _)| (j, i))
         })
-        .sum()
+        .map(|(x, y)| (col_offsets[x], row_offsets[y]))
+        .unzip();
+
+    pairwise_sum(&mut xs) + pairwise_sum(&mut ys)
 }
 
 pub struct Day11Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day11Solver {
-    type ParsedInput = Vec<Vec<char>>;
+    type ParsedInput = ByteGrid<'a>;
 
     type Part1Output = usize;
 
@@ -67,13 +73,14 @@ impl<'a> AdventOfCodeDay<'a> for Day11Solver {
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.trim().chars().collect()).collect()
+        ByteGrid::new(input).unwrap()
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use utils::rng::Rng;
 
     #[test]
     fn test_part1() {
@@ -114,4 +121,74 @@ mod test {
             82_000_210
         );
     }
+
+    // Expands every galaxy separately and sums all pairs, which is quadratic
+    // but obviously right, to check the prefix sums in `solve` against.
+    fn distance(a: (usize, usize), b: (usize, usize)) -> usize {
+        a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+    }
+
+    fn solve_pairwise(input: &ByteGrid, r: usize) -> usize {
+        let galaxies: Vec<_> = input
+            .rows()
+            .enumerate()
+            .flat_map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .filter_map(move |(j, c)| if *c == b'#' { Some((j, i)) } else { None })
+            })
+            .collect();
+
+        let mut y_expansions = vec![r; input.height()];
+        for (i, row) in input.rows().enumerate() {
+            if row.iter().any(|c| *c != b'.') {
+                y_expansions[i] = 1;
+            }
+        }
+
+        let mut x_expansions = vec![r; input.width()];
+        for (i, x_expansion) in x_expansions.iter_mut().enumerate() {
+            if input.column(i).any(|c| c != b'.') {
+                *x_expansion = 1;
+            }
+        }
+
+        galaxies
+            .iter()
+            .enumerate()
+            .flat_map(|(i, galaxy)| {
+                galaxies
+                    .iter()
+                    .skip(i + 1)
+                    .map(|other| {
+                        let x_adj_galaxy: usize = (0..galaxy.0).map(|x| x_expansions[x]).sum();
+                        let y_adj_galaxy: usize = (0..galaxy.1).map(|y| y_expansions[y]).sum();
+                        let x_adj_other: usize = (0..other.0).map(|x| x_expansions[x]).sum();
+                        let y_adj_other: usize = (0..other.1).map(|y| y_expansions[y]).sum();
+                        distance((x_adj_galaxy, y_adj_galaxy), (x_adj_other, y_adj_other))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .sum()
+    }
+
+    #[test]
+    fn test_matches_pairwise() {
+        let mut rng = Rng::new(0xd1b5_4a32_d192_ed03);
+        let rows: Vec<String> = (0..30)
+            .map(|row| {
+                (0..40)
+                    .map(|col| match rng.below(12) {
+                        0 if row % 7 != 3 && col % 5 != 2 => '#',
+                        _ => '.',
+                    })
+                    .collect()
+            })
+            .collect();
+        let input = rows.join("\n");
+        let grid = ByteGrid::new(&input).unwrap();
+        for r in [1, 2, 10, 1_000_000] {
+            assert_eq!(solve(&grid, r), solve_pairwise(&grid, r));
+        }
+    }
 }
@@ -1,6 +1,10 @@
-use std::{collections::HashMap, str::FromStr};
+use std::str::FromStr;
 
 use aoc_traits::AdventOfCodeDay;
+use utils::{
+    memo::{Cache, DenseCache, Memoized, Stats},
+    parse::{self, ParseError},
+};
 
 #[derive(Debug, Clone)]
 pub struct Line {
@@ -25,23 +29,39 @@ impl Line {
 }
 
 impl FromStr for Line {
-    type Err = ();
+    type Err = ParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        let (springs, groups) = s.split_once(' ').unwrap();
+        let (springs, groups) = parse::split(s, " ")?;
         let springs: Vec<char> = springs.chars().collect();
-        let groups = groups.split(',').map(|x| x.parse().unwrap()).collect();
+        let groups = parse::comma_list(groups).map_err(|e| e.rebase(groups, s))?;
         Ok(Line { springs, groups })
     }
 }
 
 fn arrangements(springs: &[char], groups: &[usize]) -> usize {
-    let mut cache = HashMap::new();
-    dfs(&mut cache, springs, groups, 0, 0, 0)
+    let max_group = groups.iter().max().copied().unwrap_or(0);
+    let cache = DenseCache::new([springs.len() + 1, groups.len() + 1, max_group + 1]);
+    arrangements_with(cache, springs, groups).0
+}
+
+fn arrangements_with<C: Cache<[usize; 3], usize>>(
+    cache: C,
+    springs: &[char],
+    groups: &[usize],
+) -> (usize, Stats) {
+    let mut dfs = Memoized::new(
+        cache,
+        |recurse: &mut dyn FnMut([usize; 3]) -> usize, [from, group, size]: [usize; 3]| {
+            dfs(recurse, springs, groups, from, group, size)
+        },
+    );
+    let ways = dfs.call([0, 0, 0]);
+    (ways, dfs.stats())
 }
 
 fn dfs(
-    cache: &mut HashMap<(usize, usize, usize), usize>,
+    recurse: &mut dyn FnMut([usize; 3]) -> usize,
     springs: &[char],
     groups: &[usize],
     from: usize,
@@ -63,42 +83,37 @@ fn dfs(
     match springs[from] {
         '.' => {
             if size == 0 {
-                return dfs(cache, springs, groups, from + 1, group, size);
+                return recurse([from + 1, group, size]);
             }
 
             if group >= groups.len() || size != groups[group] {
                 return 0;
             }
 
-            dfs(cache, springs, groups, from + 1, group + 1, 0)
+            recurse([from + 1, group + 1, 0])
         }
         '#' => {
             if group >= groups.len() || size + 1 > groups[group] {
                 return 0;
             }
 
-            dfs(cache, springs, groups, from + 1, group, size + 1)
+            recurse([from + 1, group, size + 1])
         }
         _ => {
-            if let Some(answer) = cache.get(&(from, group, size)).copied() {
-                return answer;
-            }
-
             let mut ways = 0;
 
             if size == 0 {
-                ways += dfs(cache, springs, groups, from + 1, group, size);
+                ways += recurse([from + 1, group, size]);
             }
 
             if group < groups.len() && size < groups[group] {
-                ways += dfs(cache, springs, groups, from + 1, group, size + 1);
+                ways += recurse([from + 1, group, size + 1]);
             }
 
             if group < groups.len() && size == groups[group] {
-                ways += dfs(cache, springs, groups, from + 1, group + 1, 0);
+                ways += recurse([from + 1, group + 1, 0]);
             }
 
-            cache.insert((from, group, size), ways);
             ways
         }
     }
@@ -129,15 +144,14 @@ impl<'a> AdventOfCodeDay<'a> for Day12Solver {
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input
-            .lines()
-            .map(|l| l.trim().parse::<Line>().unwrap())
-            .collect()
+        parse::lines(input).unwrap_or_else(|e| panic!("{}", e.describe(input)))
     }
 }
 
 #[cfg(test)]
 mod test {
+    use utils::memo::HashCache;
+
     use super::*;
 
     #[test]
@@ -171,4 +185,27 @@ mod test {
             525152
         );
     }
+
+    #[test]
+    fn test_caches_agree() {
+        let input = "
+            ???.### 1,1,3
+            .??..??...?##. 1,1,3
+            ?#?#?#?#?#?#?#? 1,3,1,6
+            ????.#...#... 4,1,1
+            ????.######..#####. 1,6,5
+            ?###???????? 3,2,1
+        ";
+        for line in Day12Solver::parse_input(input.trim()) {
+            let line = line.unfold();
+            let max_group = *line.groups.iter().max().unwrap();
+            let dims = [line.springs.len() + 1, line.groups.len() + 1, max_group + 1];
+            let (dense, dense_stats) =
+                arrangements_with(DenseCache::new(dims), &line.springs, &line.groups);
+            let (hashed, hash_stats) =
+                arrangements_with(HashCache::new(), &line.springs, &line.groups);
+            assert_eq!(dense, hashed);
+            assert_eq!(dense_stats, hash_stats);
+        }
+    }
 }
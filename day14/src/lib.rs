@@ -1,13 +1,14 @@
 use std::collections::HashMap;
 
 use aoc_traits::AdventOfCodeDay;
+use utils::grid::ByteGrid;
 
 enum Direction {
     Left,
     Right,
 }
 
-fn transpose(v: Vec<Vec<char>>) -> Vec<Vec<char>> {
+fn transpose(v: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
     let len = v[0].len();
     let mut iters: Vec<_> = v.into_iter().map(|n| n.into_iter()).collect();
     (0..len)
@@ -15,51 +16,42 @@ fn transpose(v: Vec<Vec<char>>) -> Vec<Vec<char>> {
             iters
                 .iter_mut()
                 .map(|n| n.next().unwrap())
-                .collect::<Vec<char>>()
+                .collect::<Vec<u8>>()
         })
         .collect()
 }
 
-fn calc_load(input: Vec<Vec<char>>) -> usize {
+fn calc_load(input: Vec<Vec<u8>>) -> usize {
     input
         .iter()
         .map(|row| {
             row.iter()
                 .enumerate()
-                .filter_map(|(i, c)| if *c == 'O' { Some(row.len() - i) } else { None })
+                .filter_map(|(i, c)| {
+                    if *c == b'O' {
+                        Some(row.len() - i)
+                    } else {
+                        None
+                    }
+                })
                 .sum::<usize>()
         })
         .sum()
 }
 
-fn shift(input: Vec<Vec<char>>, dir: Direction) -> Vec<Vec<char>> {
+fn shift(mut input: Vec<Vec<u8>>, dir: Direction) -> Vec<Vec<u8>> {
+    for row in input.iter_mut() {
+        for group in row.split_mut(|c| *c == b'#') {
+            group.sort_by(|a, b| match dir {
+                Direction::Left => b.cmp(a),
+                Direction::Right => a.cmp(b),
+            });
+        }
+    }
     input
-        .into_iter()
-        .map(|row| {
-            let mut shifted: Vec<_> = row
-                .iter()
-                .collect::<String>()
-                .split('#')
-                .flat_map(|g| {
-                    let mut chars: Vec<_> = g.chars().collect();
-                    chars.sort_by(|a, b| match dir {
-                        Direction::Left => b.cmp(a),
-                        Direction::Right => a.cmp(b),
-                    });
-                    chars
-                })
-                .collect();
-            for (i, c) in row.iter().enumerate() {
-                if *c == '#' {
-                    shifted.insert(i, '#');
-                }
-            }
-            shifted
-        })
-        .collect()
 }
 
-fn cycle(input: Vec<Vec<char>>) -> Vec<Vec<char>> {
+fn cycle(input: Vec<Vec<u8>>) -> Vec<Vec<u8>> {
     let north = shift(transpose(input), Direction::Left);
     let west = shift(transpose(north), Direction::Left);
     let south = shift(transpose(west), Direction::Right);
@@ -69,7 +61,7 @@ fn cycle(input: Vec<Vec<char>>) -> Vec<Vec<char>> {
 pub struct Day14Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day14Solver {
-    type ParsedInput = Vec<Vec<char>>;
+    type ParsedInput = ByteGrid<'a>;
 
     type Part1Output = usize;
 
@@ -81,7 +73,7 @@ impl<'a> AdventOfCodeDay<'a> for Day14Solver {
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
         let mut possible = HashMap::new();
-        let mut cur = input.clone();
+        let mut cur = input.to_vec();
         for i in 0..1_000_000_000 {
             cur = cycle(cur);
             if let Some(j) = possible.insert(cur.clone(), i) {
@@ -99,7 +91,7 @@ impl<'a> AdventOfCodeDay<'a> for Day14Solver {
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.trim().chars().collect()).collect()
+        ByteGrid::new(input).unwrap()
     }
 }
 
@@ -1,6 +1,7 @@
 use std::collections::HashSet;
 
 use aoc_traits::AdventOfCodeDay;
+use utils::grid::ByteGrid;
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 enum Direction {
@@ -28,33 +29,33 @@ fn update_pos(dir: &Direction, row: &mut usize, col: &mut usize) {
 }
 
 fn bounce(
-    grid: &[Vec<char>],
+    grid: &ByteGrid,
     mut row: usize,
     mut col: usize,
     mut dir: Direction,
     visited: &mut HashSet<(usize, usize, Direction)>,
 ) {
-    while row < grid.len() && col < grid[0].len() {
+    while row < grid.height() && col < grid.width() {
         if !visited.insert((row, col, dir.clone())) {
             return;
         }
 
-        match grid[row][col] {
-            '|' => match dir {
+        match grid[(row, col)] {
+            b'|' => match dir {
                 Direction::Left | Direction::Right => {
                     bounce(grid, row.wrapping_sub(1), col, Direction::Up, visited);
                     bounce(grid, row + 1, col, Direction::Down, visited);
                 }
                 _ => update_pos(&dir, &mut row, &mut col),
             },
-            '-' => match dir {
+            b'-' => match dir {
                 Direction::Up | Direction::Down => {
                     bounce(grid, row, col.wrapping_sub(1), Direction::Left, visited);
                     bounce(grid, row, col + 1, Direction::Right, visited);
                 }
                 _ => update_pos(&dir, &mut row, &mut col),
             },
-            '/' => {
+            b'/' => {
                 dir = match dir {
                     Direction::Up => Direction::Right,
                     Direction::Down => Direction::Left,
@@ -63,7 +64,7 @@ fn bounce(
                 };
                 update_pos(&dir, &mut row, &mut col);
             }
-            '\\' => {
+            b'\\' => {
                 dir = match dir {
                     Direction::Up => Direction::Left,
                     Direction::Down => Direction::Right,
@@ -77,7 +78,7 @@ fn bounce(
     }
 }
 
-fn calc_energy(input: &[Vec<char>], row: usize, col: usize, dir: Direction) -> usize {
+fn calc_energy(input: &ByteGrid, row: usize, col: usize, dir: Direction) -> usize {
     let mut visited = HashSet::new();
     bounce(input, row, col, dir, &mut visited);
     let mut energized = HashSet::new();
@@ -90,7 +91,7 @@ fn calc_energy(input: &[Vec<char>], row: usize, col: usize, dir: Direction) -> u
 pub struct Day16Solver;
 
 impl<'a> AdventOfCodeDay<'a> for Day16Solver {
-    type ParsedInput = Vec<Vec<char>>;
+    type ParsedInput = ByteGrid<'a>;
 
     type Part1Output = usize;
 
@@ -102,20 +103,20 @@ impl<'a> AdventOfCodeDay<'a> for Day16Solver {
 
     fn solve_part2(input: &Self::ParsedInput) -> Self::Part2Output {
         let mut energy = 0usize;
-        for row in 0..input.len() {
+        for row in 0..input.height() {
             energy = energy.max(calc_energy(input, row, 0, Direction::Right));
-            energy = energy.max(calc_energy(input, row, input[0].len() - 1, Direction::Left));
+            energy = energy.max(calc_energy(input, row, input.width() - 1, Direction::Left));
         }
 
-        for col in 0..input[0].len() {
+        for col in 0..input.width() {
             energy = energy.max(calc_energy(input, 0, col, Direction::Down));
-            energy = energy.max(calc_energy(input, input.len() - 1, col, Direction::Up));
+            energy = energy.max(calc_energy(input, input.height() - 1, col, Direction::Up));
         }
         energy
     }
 
     fn parse_input(input: &'a str) -> Self::ParsedInput {
-        input.lines().map(|l| l.trim().chars().collect()).collect()
+        ByteGrid::new(input).unwrap()
     }
 }
 
@@ -53,3 +53,13 @@ impl AdventOfCodeSolutions for AoC2023 {
 
     type Day25 = ();
 }
+
+/// A day's own view of `input`, or `None` for days that have nothing beyond the answers.
+pub fn explain(day: usize, input: &str) -> Option<String> {
+    match day {
+        2 => Some(day02::explain(input)),
+        7 => Some(day07::explain(input)),
+        10 => Some(day10::explain(input)),
+        _ => None,
+    }
+}
@@ -10,6 +10,8 @@ struct AoCRunner {
     day: usize,
     #[clap(short, long)]
     input: PathBuf,
+    #[clap(long)]
+    explain: bool,
 }
 
 fn main() -> Result<()> {
@@ -19,5 +21,11 @@ fn main() -> Result<()> {
 
     meta::AoC2023::solve_day(args.day, &input).map_err(|e| color_eyre::eyre::eyre!(e))?;
 
+    if args.explain {
+        let explanation = meta::explain(args.day, &input)
+            .ok_or_else(|| color_eyre::eyre::eyre!("day {} has no --explain view", args.day))?;
+        print!("{}", explanation);
+    }
+
     Ok(())
 }
@@ -0,0 +1,120 @@
+//! Lattice polygon helpers: shoelace area, Pick's theorem and point-in-polygon.
+
+fn gcd(a: i64, b: i64) -> i64 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+/// A closed polygon on the integer lattice, given as `(x, y)` vertices in order.
+/// The last vertex connects back to the first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Polygon {
+    vertices: Vec<(i64, i64)>,
+}
+
+impl Polygon {
+    pub fn new(vertices: Vec<(i64, i64)>) -> Self {
+        Polygon { vertices }
+    }
+
+    pub fn vertices(&self) -> &[(i64, i64)] {
+        &self.vertices
+    }
+
+    fn edges(&self) -> impl Iterator<Item = ((i64, i64), (i64, i64))> + '_ {
+        self.vertices
+            .iter()
+            .copied()
+            .zip(self.vertices.iter().copied().cycle().skip(1))
+    }
+
+    /// Twice the enclosed area, which is always an integer for lattice polygons.
+    pub fn double_area(&self) -> i64 {
+        self.edges()
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<i64>()
+            .abs()
+    }
+
+    /// Enclosed area by the shoelace formula.
+    pub fn area(&self) -> f64 {
+        self.double_area() as f64 / 2.0
+    }
+
+    /// Number of lattice points on the edges.
+    pub fn boundary_points(&self) -> i64 {
+        self.edges().map(|(a, b)| gcd(b.0 - a.0, b.1 - a.1)).sum()
+    }
+
+    /// Number of lattice points strictly inside, by Pick's theorem `A = I + B/2 - 1`.
+    pub fn interior_points(&self) -> i64 {
+        (self.double_area() - self.boundary_points() + 2) / 2
+    }
+
+    pub fn on_boundary(&self, p: (i64, i64)) -> bool {
+        self.edges().any(|(a, b)| {
+            let cross = (b.0 - a.0) * (p.1 - a.1) - (b.1 - a.1) * (p.0 - a.0);
+            cross == 0
+                && a.0.min(b.0) <= p.0
+                && p.0 <= a.0.max(b.0)
+                && a.1.min(b.1) <= p.1
+                && p.1 <= a.1.max(b.1)
+        })
+    }
+
+    /// Whether `p` lies strictly inside, by even-odd ray casting towards `+x`.
+    /// Points on the boundary are not contained.
+    pub fn contains(&self, p: (i64, i64)) -> bool {
+        if self.on_boundary(p) {
+            return false;
+        }
+
+        self.edges()
+            .filter(|(a, b)| (a.1 > p.1) != (b.1 > p.1))
+            .filter(|(a, b)| {
+                // p lies left of the crossing at x = a.0 + (p.1 - a.1) * dx / dy
+                let dy = b.1 - a.1;
+                let lhs = (p.0 - a.0) * dy;
+                let rhs = (p.1 - a.1) * (b.0 - a.0);
+                if dy > 0 {
+                    lhs < rhs
+                } else {
+                    lhs > rhs
+                }
+            })
+            .count()
+            % 2
+            == 1
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_square() {
+        let square = Polygon::new(vec![(0, 0), (4, 0), (4, 4), (0, 4)]);
+        assert_eq!(square.double_area(), 32);
+        assert_eq!(square.area(), 16.0);
+        assert_eq!(square.boundary_points(), 16);
+        assert_eq!(square.interior_points(), 9);
+        assert!(square.contains((2, 2)));
+        assert!(!square.contains((4, 2)));
+        assert!(!square.contains((5, 2)));
+    }
+
+    #[test]
+    fn test_pick_matches_ray_casting() {
+        // clockwise, concave, with a triangle edge
+        let polygon = Polygon::new(vec![(0, 0), (0, 6), (3, 3), (6, 6), (6, 0), (3, 2)]);
+        let inside = (-1..8)
+            .flat_map(|x| (-1..8).map(move |y| (x, y)))
+            .filter(|p| polygon.contains(*p))
+            .count();
+        assert_eq!(inside as i64, polygon.interior_points());
+    }
+}
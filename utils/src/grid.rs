@@ -0,0 +1,137 @@
+//! Zero-copy grid view over the puzzle input.
+
+use std::ops::Index;
+
+/// A rectangular grid of bytes borrowed from the input text.
+///
+/// Rows are read in place: row `r` starts `r * stride` bytes after the first
+/// one, which holds as long as every line has the same width and the same
+/// amount of leading whitespace (the first line may have its indentation
+/// trimmed already, as the tests do with `input.trim()`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteGrid<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+    stride: usize,
+}
+
+impl<'a> ByteGrid<'a> {
+    /// Returns `None` if the rows are ragged or not evenly spaced.
+    pub fn new(input: &'a str) -> Option<Self> {
+        let base = input.as_ptr() as usize;
+        let mut rows = input
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty())
+            .map(|l| (l.as_ptr() as usize - base, l.len()));
+
+        let (first, width) = rows.next()?;
+        let mut height = 1;
+        let mut stride = width + 1;
+        for (offset, len) in rows {
+            if height == 1 {
+                stride = offset - first;
+            }
+            if len != width || offset != first + height * stride {
+                return None;
+            }
+            height += 1;
+        }
+
+        let end = first + (height - 1) * stride + width;
+        Some(ByteGrid {
+            data: &input.as_bytes()[first..end],
+            width,
+            height,
+            stride,
+        })
+    }
+
+    #[inline]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    #[inline]
+    pub fn get(&self, row: usize, col: usize) -> Option<u8> {
+        (row < self.height && col < self.width).then(|| self.data[row * self.stride + col])
+    }
+
+    #[inline]
+    pub fn row(&self, row: usize) -> &'a [u8] {
+        let start = row * self.stride;
+        &self.data[start..start + self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &'a [u8]> + ExactSizeIterator + '_ {
+        (0..self.height).map(|row| self.row(row))
+    }
+
+    pub fn column(
+        &self,
+        col: usize,
+    ) -> impl DoubleEndedIterator<Item = u8> + ExactSizeIterator + '_ {
+        (0..self.height).map(move |row| self[(row, col)])
+    }
+
+    /// `(row, col)` of the first occurrence of `b`.
+    pub fn find(&self, b: u8) -> Option<(usize, usize)> {
+        (0..self.height).find_map(|row| {
+            self.row(row)
+                .iter()
+                .position(|c| *c == b)
+                .map(|col| (row, col))
+        })
+    }
+
+    /// Copies the grid into owned rows, for solvers that mutate it.
+    pub fn to_vec(&self) -> Vec<Vec<u8>> {
+        self.rows().map(<[u8]>::to_vec).collect()
+    }
+}
+
+impl Index<(usize, usize)> for ByteGrid<'_> {
+    type Output = u8;
+
+    /// Indexes by `(row, col)`.
+    #[inline]
+    fn index(&self, (row, col): (usize, usize)) -> &Self::Output {
+        assert!(col < self.width, "column {} out of bounds", col);
+        &self.data[row * self.stride + col]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_indented() {
+        let input = "
+            #..
+            .#.
+            ..#
+        ";
+        let grid = ByteGrid::new(input.trim()).unwrap();
+        assert_eq!((grid.height(), grid.width()), (3, 3));
+        assert_eq!(grid.row(1), b".#.");
+        assert_eq!(grid[(2, 2)], b'#');
+        assert_eq!(grid.get(3, 0), None);
+        assert_eq!(grid.column(0).collect::<Vec<_>>(), b"#..");
+        assert_eq!(grid.find(b'#'), Some((0, 0)));
+    }
+
+    #[test]
+    fn test_crlf_and_ragged() {
+        let grid = ByteGrid::new("ab\r\ncd\r\n").unwrap();
+        assert_eq!(grid.to_vec(), vec![b"ab".to_vec(), b"cd".to_vec()]);
+        assert_eq!(ByteGrid::new("abc\nde\n"), None);
+        assert_eq!(ByteGrid::new(""), None);
+    }
+}
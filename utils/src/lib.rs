@@ -0,0 +1,5 @@
+pub mod geometry;
+pub mod grid;
+pub mod memo;
+pub mod parse;
+pub mod rng;
@@ -0,0 +1,217 @@
+//! Memoization for recursive solvers.
+//!
+//! A [`Memoized`] wraps a recursive function together with a [`Cache`]. The
+//! function receives a `recurse` callback instead of calling itself, so every
+//! recursive call goes through the cache:
+//!
+//! ```
+//! use utils::memo::{HashCache, Memoized};
+//!
+//! fn fib(recurse: &mut dyn FnMut(u64) -> u64, n: u64) -> u64 {
+//!     if n < 2 {
+//!         n
+//!     } else {
+//!         recurse(n - 1) + recurse(n - 2)
+//!     }
+//! }
+//!
+//! let mut fib = Memoized::new(HashCache::new(), fib);
+//! assert_eq!(fib.call(80), 23_416_728_348_467_685);
+//! assert_eq!(fib.stats().misses, 81);
+//! ```
+
+use std::{collections::HashMap, hash::Hash};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub hits: usize,
+    pub misses: usize,
+}
+
+impl Stats {
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            total => self.hits as f64 / total as f64,
+        }
+    }
+}
+
+pub trait CacheStats {
+    fn stats(&self) -> Stats;
+}
+
+pub trait Cache<K, V>: CacheStats {
+    /// Looks up `key`, counting the lookup as a hit or a miss.
+    fn lookup(&mut self, key: &K) -> Option<V>;
+
+    fn store(&mut self, key: K, value: V);
+}
+
+/// Cache for arbitrary hashable keys.
+#[derive(Debug, Clone)]
+pub struct HashCache<K, V> {
+    values: HashMap<K, V>,
+    stats: Stats,
+}
+
+impl<K, V> HashCache<K, V> {
+    pub fn new() -> Self {
+        HashCache {
+            values: HashMap::new(),
+            stats: Stats::default(),
+        }
+    }
+}
+
+impl<K, V> Default for HashCache<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Hash + Eq, V: Clone> Cache<K, V> for HashCache<K, V> {
+    fn lookup(&mut self, key: &K) -> Option<V> {
+        let value = self.values.get(key).cloned();
+        match value {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        value
+    }
+
+    fn store(&mut self, key: K, value: V) {
+        self.values.insert(key, value);
+    }
+}
+
+impl<K, V> CacheStats for HashCache<K, V> {
+    fn stats(&self) -> Stats {
+        self.stats
+    }
+}
+
+/// Cache for keys made of `N` bounded integers, stored in one flat array.
+///
+/// Key `[a, b, c]` with `dims` `[da, db, dc]` requires `a < da`, `b < db`
+/// and `c < dc`.
+#[derive(Debug, Clone)]
+pub struct DenseCache<V, const N: usize> {
+    dims: [usize; N],
+    values: Vec<Option<V>>,
+    stats: Stats,
+}
+
+impl<V: Clone, const N: usize> DenseCache<V, N> {
+    pub fn new(dims: [usize; N]) -> Self {
+        DenseCache {
+            dims,
+            values: vec![None; dims.iter().product()],
+            stats: Stats::default(),
+        }
+    }
+
+    fn index(&self, key: &[usize; N]) -> usize {
+        key.iter().zip(self.dims).fold(0, |acc, (k, dim)| {
+            assert!(*k < dim, "key {:?} out of bounds {:?}", key, self.dims);
+            acc * dim + k
+        })
+    }
+}
+
+impl<V: Clone, const N: usize> Cache<[usize; N], V> for DenseCache<V, N> {
+    fn lookup(&mut self, key: &[usize; N]) -> Option<V> {
+        let value = self.values[self.index(key)].clone();
+        match value {
+            Some(_) => self.stats.hits += 1,
+            None => self.stats.misses += 1,
+        }
+        value
+    }
+
+    fn store(&mut self, key: [usize; N], value: V) {
+        let index = self.index(&key);
+        self.values[index] = Some(value);
+    }
+}
+
+impl<V, const N: usize> CacheStats for DenseCache<V, N> {
+    fn stats(&self) -> Stats {
+        self.stats
+    }
+}
+
+/// A recursive function `f` whose results are cached in `C`.
+pub struct Memoized<C, F> {
+    cache: C,
+    f: F,
+}
+
+impl<C, F> Memoized<C, F> {
+    pub fn new(cache: C, f: F) -> Self {
+        Memoized { cache, f }
+    }
+
+    pub fn call<K, V>(&mut self, key: K) -> V
+    where
+        K: Clone,
+        V: Clone,
+        C: Cache<K, V>,
+        F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+    {
+        call(&mut self.cache, &self.f, key)
+    }
+
+    pub fn stats(&self) -> Stats
+    where
+        C: CacheStats,
+    {
+        self.cache.stats()
+    }
+}
+
+fn call<K, V, C, F>(cache: &mut C, f: &F, key: K) -> V
+where
+    K: Clone,
+    V: Clone,
+    C: Cache<K, V>,
+    F: Fn(&mut dyn FnMut(K) -> V, K) -> V,
+{
+    if let Some(value) = cache.lookup(&key) {
+        return value;
+    }
+
+    let value = f(&mut |key| call(cache, f, key), key.clone());
+    cache.store(key, value.clone());
+    value
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn binomial(recurse: &mut dyn FnMut([usize; 2]) -> u64, [n, k]: [usize; 2]) -> u64 {
+        if k == 0 || k == n {
+            1
+        } else {
+            recurse([n - 1, k - 1]) + recurse([n - 1, k])
+        }
+    }
+
+    #[test]
+    fn test_hash_and_dense_agree() {
+        let mut hashed = Memoized::new(HashCache::new(), binomial);
+        let mut dense = Memoized::new(DenseCache::new([61, 31]), binomial);
+        assert_eq!(hashed.call([60, 30]), 118_264_581_564_861_424);
+        assert_eq!(dense.call([60, 30]), 118_264_581_564_861_424);
+        assert_eq!(hashed.stats(), dense.stats());
+        assert!(dense.stats().hits > 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_dense_out_of_bounds() {
+        let mut dense = Memoized::new(DenseCache::new([2, 2]), binomial);
+        dense.call([2, 1]);
+    }
+}
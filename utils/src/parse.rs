@@ -0,0 +1,327 @@
+//! Small helpers for the `Label: n n n` style inputs most days share.
+//!
+//! A [`ParseError`] records where the offending token starts, as a byte
+//! offset into the string the failing helper was given. Helpers that work on
+//! a slice of the input can move that offset out to the whole input with
+//! [`ParseError::rebase`], after which [`ParseError::position`] gives the
+//! line and column.
+
+use std::{error::Error, fmt, str::FromStr};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    InvalidValue,
+    MissingSeparator(&'static str),
+    UnexpectedKey(String),
+    WrongCount { expected: usize, found: usize },
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub token: String,
+    offset: usize,
+}
+
+/// Byte offset of `inner` in `outer`, which it must be a slice of.
+fn offset_in(inner: &str, outer: &str) -> usize {
+    let offset = (inner.as_ptr() as usize).wrapping_sub(outer.as_ptr() as usize);
+    assert!(
+        offset <= outer.len() && offset + inner.len() <= outer.len(),
+        "`{}` is not a slice of `{}`",
+        inner,
+        outer
+    );
+    offset
+}
+
+impl ParseError {
+    fn new(kind: ParseErrorKind, token: &str, within: &str) -> Self {
+        ParseError {
+            kind,
+            token: token.to_string(),
+            offset: offset_in(token, within),
+        }
+    }
+
+    /// Byte offset of the offending token in the string the error was reported for.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Makes the offset relative to `outer`, for an error reported while
+    /// parsing `inner`, a slice of `outer`.
+    ///
+    /// Panics if `inner` is not a slice of `outer`.
+    pub fn rebase(mut self, inner: &str, outer: &str) -> Self {
+        self.offset += offset_in(inner, outer);
+        self
+    }
+
+    /// 1-based line and column of the offending token in `input`, the string
+    /// the error was reported for or rebased to.
+    pub fn position(&self, input: &str) -> Option<(usize, usize)> {
+        let before = input.get(..self.offset)?;
+        let line = before.matches('\n').count() + 1;
+        let column = before.len() - before.rfind('\n').map_or(0, |i| i + 1) + 1;
+        Some((line, column))
+    }
+
+    /// The error prefixed with its line and column in `input`, for panics in
+    /// parsers that cannot return it.
+    pub fn describe(&self, input: &str) -> String {
+        match self.position(input) {
+            Some((line, column)) => format!("line {}, column {}: {}", line, column, self),
+            None => format!("byte {}: {}", self.offset, self),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            ParseErrorKind::InvalidValue => write!(f, "invalid value `{}`", self.token),
+            ParseErrorKind::MissingSeparator(sep) => {
+                write!(f, "missing `{}` in `{}`", sep, self.token)
+            }
+            ParseErrorKind::UnexpectedKey(expected) => {
+                write!(f, "expected key `{}`, found `{}`", expected, self.token)
+            }
+            ParseErrorKind::WrongCount { expected, found } => write!(
+                f,
+                "expected {} values, found {} in `{}`",
+                expected, found, self.token
+            ),
+            ParseErrorKind::Empty => write!(f, "unexpected end of input"),
+        }
+    }
+}
+
+impl Error for ParseError {}
+
+/// Parses a single trimmed value, e.g. `usize` or `i64`.
+pub fn value<T: FromStr>(s: &str) -> Result<T, ParseError> {
+    let t = s.trim();
+    t.parse()
+        .map_err(|_| ParseError::new(ParseErrorKind::InvalidValue, t, s))
+}
+
+/// Splits `s` at the first `sep`, failing if it does not occur.
+pub fn split<'a>(s: &'a str, sep: &'static str) -> Result<(&'a str, &'a str), ParseError> {
+    s.split_once(sep)
+        .ok_or_else(|| ParseError::new(ParseErrorKind::MissingSeparator(sep), s, s))
+}
+
+/// Splits a `key: value` line and trims both sides.
+pub fn key_value(s: &str) -> Result<(&str, &str), ParseError> {
+    let t = s.trim();
+    let (key, value) = split(t, ":").map_err(|e| e.rebase(t, s))?;
+    Ok((key.trim(), value.trim()))
+}
+
+/// Returns the value of a `key: value` line, checking that the key matches.
+pub fn header<'a>(s: &'a str, key: &str) -> Result<&'a str, ParseError> {
+    let (found, value) = key_value(s)?;
+    if found != key {
+        return Err(ParseError::new(
+            ParseErrorKind::UnexpectedKey(key.to_string()),
+            found,
+            s,
+        ));
+    }
+    Ok(value)
+}
+
+/// Parses the number of a label like `Game 12` or `Card   3`.
+pub fn labelled<T: FromStr>(s: &str, label: &str) -> Result<T, ParseError> {
+    let t = s.trim();
+    match t.strip_prefix(label) {
+        Some(rest) if rest.starts_with(char::is_whitespace) => {
+            value(rest).map_err(|e| e.rebase(rest, s))
+        }
+        _ => Err(ParseError::new(
+            ParseErrorKind::UnexpectedKey(label.to_string()),
+            t,
+            s,
+        )),
+    }
+}
+
+/// Parses every item of a `sep` separated list with `f`, whose errors are
+/// relative to the item.
+pub fn list<'a, T>(
+    s: &'a str,
+    sep: char,
+    mut f: impl FnMut(&'a str) -> Result<T, ParseError>,
+) -> Result<Vec<T>, ParseError> {
+    s.split(sep)
+        .map(str::trim)
+        .map(|item| f(item).map_err(|e| e.rebase(item, s)))
+        .collect()
+}
+
+/// Parses every trimmed line of `s` with `T::from_str`, whose errors are
+/// relative to the line.
+pub fn lines<T: FromStr<Err = ParseError>>(s: &str) -> Result<Vec<T>, ParseError> {
+    s.lines()
+        .map(str::trim)
+        .map(|line| line.parse().map_err(|e: ParseError| e.rebase(line, s)))
+        .collect()
+}
+
+/// Parses a comma separated list like `1,1,3`.
+pub fn comma_list<T: FromStr>(s: &str) -> Result<Vec<T>, ParseError> {
+    list(s, ',', value)
+}
+
+/// Parses a whitespace separated list of signed or unsigned numbers.
+pub fn numbers<T: FromStr>(s: &str) -> Result<Vec<T>, ParseError> {
+    s.split_whitespace()
+        .map(|t| value(t).map_err(|e| e.rebase(t, s)))
+        .collect()
+}
+
+/// Parses the whitespace separated tokens of `s` run together as one value,
+/// e.g. `7  15   30` as `71530`.
+pub fn concatenated<T: FromStr>(s: &str) -> Result<T, ParseError> {
+    let t = s.trim();
+    let joined: String = t.split_whitespace().collect();
+    joined
+        .parse()
+        .map_err(|_| ParseError::new(ParseErrorKind::InvalidValue, t, s))
+}
+
+/// Like [`numbers`], but requires exactly `N` of them.
+pub fn number_array<T: FromStr, const N: usize>(s: &str) -> Result<[T; N], ParseError> {
+    let numbers = numbers(s)?;
+    let found = numbers.len();
+    numbers.try_into().map_err(|_| {
+        ParseError::new(
+            ParseErrorKind::WrongCount { expected: N, found },
+            s.trim(),
+            s,
+        )
+    })
+}
+
+/// Iterates over the blocks of `s` separated by blank lines.
+pub fn blocks(s: &str) -> impl Iterator<Item = &str> {
+    let mut rest = s;
+    std::iter::from_fn(move || {
+        let mut start = None;
+        let mut end = 0;
+        let mut consumed = rest.len();
+        let mut offset = 0;
+        for line in rest.split_inclusive('\n') {
+            let blank = line.trim().is_empty();
+            match (start, blank) {
+                (None, false) => start = Some(offset),
+                (Some(_), true) => {
+                    consumed = offset;
+                    break;
+                }
+                _ => {}
+            }
+            offset += line.len();
+            if !blank {
+                end = offset;
+            }
+        }
+        let block = start.map(|start| rest[start..end].trim());
+        rest = &rest[consumed..];
+        block
+    })
+}
+
+/// Returns the next item of `iter`, failing at the end of `context`, the
+/// string the items come from, if there is none.
+pub fn next<'a>(
+    iter: &mut impl Iterator<Item = &'a str>,
+    context: &'a str,
+) -> Result<&'a str, ParseError> {
+    iter.next()
+        .ok_or_else(|| ParseError::new(ParseErrorKind::Empty, &context[context.len()..], context))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(numbers::<i64>(" 0 -3  6 ").unwrap(), vec![0, -3, 6]);
+        assert_eq!(number_array::<usize, 3>("50 98 2").unwrap(), [50, 98, 2]);
+        assert_eq!(comma_list::<usize>("1,1,3").unwrap(), vec![1, 1, 3]);
+
+        let err = number_array::<usize, 3>("50 98").unwrap_err();
+        assert_eq!(
+            err.kind,
+            ParseErrorKind::WrongCount {
+                expected: 3,
+                found: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_headers() {
+        assert_eq!(header("Time:      7  15", "Time").unwrap(), "7  15");
+        assert_eq!(labelled::<usize>("Card   3", "Card").unwrap(), 3);
+        assert!(header("Distance: 9", "Time").is_err());
+        assert!(labelled::<usize>("Cards 3", "Card").is_err());
+    }
+
+    #[test]
+    fn test_blocks() {
+        let input = "a\nb\n\n  \nc\n\n\nd e\n";
+        assert_eq!(blocks(input).collect::<Vec<_>>(), vec!["a\nb", "c", "d e"]);
+        assert_eq!(blocks("\n\n").count(), 0);
+    }
+
+    #[test]
+    fn test_error_position() {
+        let input = "Time: 7 15\nDistance: 9 x0";
+        let line = input.lines().nth(1).unwrap();
+        let distances = header(line, "Distance").unwrap();
+        let err = numbers::<usize>(distances).unwrap_err();
+        assert_eq!(err.kind, ParseErrorKind::InvalidValue);
+        assert_eq!(err.token, "x0");
+        assert_eq!(err.offset(), 2);
+
+        let err = err.rebase(distances, input);
+        assert_eq!(err.position(input), Some((2, 13)));
+        assert_eq!(err.position("too short"), None);
+        assert_eq!(err.describe(input), "line 2, column 13: invalid value `x0`");
+
+        // errors from a temporary only carry their offset into it
+        let joined: String = distances.split_whitespace().collect();
+        let err = value::<usize>(&joined).unwrap_err();
+        assert_eq!((err.token.as_str(), err.offset()), ("9x0", 0));
+
+        let err = concatenated::<usize>(distances).unwrap_err();
+        assert_eq!((err.token.as_str(), err.offset()), ("9 x0", 0));
+        assert_eq!(concatenated::<usize>(" 7  15   30").unwrap(), 71530);
+    }
+
+    #[test]
+    fn test_lines() {
+        #[derive(Debug, PartialEq)]
+        struct Pair(usize, usize);
+        impl FromStr for Pair {
+            type Err = ParseError;
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                let [a, b] = number_array(s)?;
+                Ok(Pair(a, b))
+            }
+        }
+
+        let input = "1 2\n  3 4\n5 six";
+        let err = lines::<Pair>(input).unwrap_err();
+        assert_eq!(err.position(input), Some((3, 3)));
+        assert_eq!(
+            lines::<Pair>("1 2\n  3 4").unwrap(),
+            vec![Pair(1, 2), Pair(3, 4)]
+        );
+    }
+}
@@ -0,0 +1,64 @@
+//! A small seeded generator for randomised tests.
+//!
+//! [`Rng`] is Marsaglia's xorshift64: not suitable for anything but
+//! generating test inputs, but deterministic, so a failing case can be
+//! reproduced from its seed.
+//!
+//! ```
+//! use utils::rng::Rng;
+//!
+//! let mut rng = Rng::new(42);
+//! let roll = rng.below(6);
+//! assert!(roll < 6);
+//! ```
+
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    /// A generator starting from `seed`, which must not be zero.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift never leaves a zero state");
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// A value in `0..n`, slightly biased towards small values unless `n` is
+    /// a power of two.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_reproducible() {
+        let mut a = Rng::new(0x2545_f491_4f6c_dd1d);
+        let mut b = a.clone();
+        let first: Vec<_> = (0..100).map(|_| a.next_u64()).collect();
+        let second: Vec<_> = (0..100).map(|_| b.next_u64()).collect();
+        assert_eq!(first, second);
+        assert!(first.iter().all(|x| *x != 0));
+    }
+
+    #[test]
+    fn test_below() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 10];
+        for _ in 0..1000 {
+            seen[rng.below(10)] = true;
+        }
+        assert!(seen.iter().all(|s| *s));
+    }
+}